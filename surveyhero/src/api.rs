@@ -1,125 +1,504 @@
-use regex::Regex;
-use reqwest::blocking::Client as Reqwest;
-use serde::Deserialize;
-use std::sync::LazyLock;
-use std::time::{Duration, SystemTime};
+use crate::cassette::{Cassette, CassetteRequest};
+use crate::error::{Error, Result};
+use crate::normalize::Normalizer;
+use crate::rate_limit::RateLimiter;
+use crate::retry::{is_idempotent, retry_after, RetryPolicy};
+use reqwest::{Client as Reqwest, Request, RequestBuilder, Response as HttpResponse, StatusCode};
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::future::Future;
+use std::sync::Arc;
 
+/// Blocking SurveyHero API client, a wrapper around [`AsyncClient`] with its own runtime.
 pub struct Client {
+    inner: AsyncClient,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl Client {
+    pub fn new(username: String, password: String) -> Self {
+        AsyncClient::new(username, password).into()
+    }
+
+    /// Replaces the default [`RetryPolicy`].
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.inner = self.inner.with_retry_policy(retry);
+        self
+    }
+
+    /// Sends all requests to `base_url` instead of [`DEFAULT_BASE_URL`].
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.inner = self.inner.with_base_url(base_url);
+        self
+    }
+
+    /// Records the traffic to, or replays it from, `cassette`.
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.inner = self.inner.with_cassette(cassette);
+        self
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Fetches all surveys of the account, following all pages.
+    pub fn fetch_surveys(&mut self) -> Result<Vec<Survey>> {
+        self.block_on(self.inner.fetch_surveys())
+    }
+
+    /// Fetches the status, dates and response count of a survey.
+    pub fn fetch_survey(&mut self, survey_id: usize) -> Result<SurveyDetails> {
+        self.block_on(self.inner.fetch_survey(survey_id))
+    }
+
+    /// Fetches all languages of the survey, including the default and inactive ones.
+    pub fn fetch_languages(&mut self, survey_id: usize) -> Result<Vec<Language>> {
+        self.block_on(self.inner.fetch_languages(survey_id))
+    }
+
+    pub fn fetch_secondary_languages(&mut self, survey_id: usize) -> Result<Vec<Language>> {
+        self.block_on(self.inner.fetch_secondary_languages(survey_id))
+    }
+
+    pub fn fetch_questions(
+        &mut self,
+        survey_id: usize,
+        language: Option<String>,
+    ) -> Result<Vec<Question>> {
+        self.block_on(self.inner.fetch_questions(survey_id, language))
+    }
+
+    /// Fetches the skip logic of the survey.
+    pub fn fetch_conditions(&mut self, survey_id: usize) -> Result<Vec<Condition>> {
+        self.block_on(self.inner.fetch_conditions(survey_id))
+    }
+
+    /// Fetches all elements of the survey in order: texts, page breaks and questions.
+    pub fn fetch_elements(
+        &mut self,
+        survey_id: usize,
+        language: Option<String>,
+    ) -> Result<Vec<Element>> {
+        self.block_on(self.inner.fetch_elements(survey_id, language))
+    }
+
+    /// Fetches all responses of the survey, following all pages.
+    pub fn fetch_responses(&mut self, survey_id: usize) -> Result<Vec<Response>> {
+        self.block_on(self.inner.fetch_responses(survey_id))
+    }
+
+    /// Creates a question at the end of the survey and returns its ID.
+    pub fn create_question(&mut self, survey_id: usize, question: &QuestionPayload) -> Result<u64> {
+        self.block_on(self.inner.create_question(survey_id, question))
+    }
+
+    /// Replaces the texts and answers of an existing question, in `language` if given and in the
+    /// default language otherwise.
+    pub fn update_question(
+        &mut self,
+        survey_id: usize,
+        question_id: u64,
+        question: &QuestionPayload,
+        language: Option<String>,
+    ) -> Result<()> {
+        self.block_on(
+            self.inner
+                .update_question(survey_id, question_id, question, language),
+        )
+    }
+
+    /// Fetches the links that let translators edit the texts of a language.
+    pub fn fetch_translation_links(&mut self, survey_id: usize) -> Result<Vec<TranslationLink>> {
+        self.block_on(self.inner.fetch_translation_links(survey_id))
+    }
+
+    /// Creates a link that lets translators edit the texts of `language`.
+    pub fn create_translation_link(
+        &mut self,
+        survey_id: usize,
+        language: &str,
+    ) -> Result<TranslationLink> {
+        self.block_on(self.inner.create_translation_link(survey_id, language))
+    }
+
+    /// Removes access for a translation link.
+    pub fn revoke_translation_link(&mut self, survey_id: usize, link_id: u64) -> Result<()> {
+        self.block_on(self.inner.revoke_translation_link(survey_id, link_id))
+    }
+}
+
+impl From<AsyncClient> for Client {
+    fn from(inner: AsyncClient) -> Self {
+        Self {
+            inner,
+            runtime: tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("cannot create the Tokio runtime"),
+        }
+    }
+}
+
+/// Asynchronous SurveyHero API client. It is cheap to clone, and all clones share one
+/// [`RateLimiter`], so requests sent concurrently from several tasks stay within the rate limit
+/// of SurveyHero together.
+#[derive(Clone)]
+pub struct AsyncClient {
     username: String,
     password: String,
     inner: Reqwest,
-    last_request: Option<SystemTime>,
+    base_url: String,
+    limiter: Arc<RateLimiter>,
+    retry: RetryPolicy,
+    cassette: Option<Arc<Cassette>>,
 }
 
-impl Client {
+/// The SurveyHero API, used unless another base URL is configured.
+pub const DEFAULT_BASE_URL: &str = "https://api.surveyhero.com/v1";
+
+impl AsyncClient {
     pub fn new(username: String, password: String) -> Self {
-        let inner = Reqwest::new();
         Self {
             username,
             password,
-            inner,
-            last_request: None,
+            inner: Reqwest::new(),
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            limiter: Arc::new(RateLimiter::surveyhero()),
+            retry: RetryPolicy::default(),
+            cassette: None,
         }
     }
 
-    pub fn fetch_surveys(&mut self) -> anyhow::Result<Vec<Survey>> {
-        self.rate_limit();
-        let response = self
-            .inner
-            .get("https://api.surveyhero.com/v1/surveys")
-            .basic_auth(&self.username, Some(&self.password))
-            .send()?;
-        let status = response.status();
-        if !status.is_success() {
-            let text = response.text()?;
-            return Err(anyhow::anyhow!(
-                "HTTP error status {status} while fetching surveys:\n{text}"
-            ));
-        }
-        let surveys: Surveys = response.json()?;
-        Ok(surveys.surveys)
+    /// Replaces the default [`RetryPolicy`].
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
     }
 
-    pub fn fetch_secondary_languages(&mut self, survey_id: usize) -> anyhow::Result<Vec<Language>> {
-        self.rate_limit();
-        let response = self
-            .inner
-            .get(format!(
-                "https://api.surveyhero.com/v1/surveys/{survey_id}/languages"
-            ))
-            .basic_auth(&self.username, Some(&self.password))
-            .send()?
-            .error_for_status()?;
-        let surveys: Languages = response.json()?;
-        Ok(surveys
-            .languages
+    /// Sends all requests to `base_url` (e.g. a mock server) instead of [`DEFAULT_BASE_URL`].
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Records the traffic to, or replays it from, `cassette`. A replaying client never
+    /// accesses the network, so its credentials do not matter.
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(Arc::new(cassette));
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    /// Fetches all surveys of the account, following all pages.
+    pub async fn fetch_surveys(&self) -> Result<Vec<Survey>> {
+        self.get_all::<Surveys>(self.url("/surveys"), "surveys")
+            .await
+    }
+
+    /// Fetches the status, dates and response count of a survey.
+    pub async fn fetch_survey(&self, survey_id: usize) -> Result<SurveyDetails> {
+        let survey: SurveyWrapper = self
+            .get_json(self.url(&format!("/surveys/{survey_id}")), "survey")
+            .await?;
+        Ok(survey.survey)
+    }
+
+    /// Fetches all languages of the survey, including the default and inactive ones.
+    pub async fn fetch_languages(&self, survey_id: usize) -> Result<Vec<Language>> {
+        let languages: Languages = self
+            .get_json(
+                self.url(&format!("/surveys/{survey_id}/languages")),
+                "languages",
+            )
+            .await?;
+        Ok(languages.languages)
+    }
+
+    pub async fn fetch_secondary_languages(&self, survey_id: usize) -> Result<Vec<Language>> {
+        Ok(self
+            .fetch_languages(survey_id)
+            .await?
             .into_iter()
             .filter(|l| !l.is_default && l.is_active)
             .collect())
     }
 
-    pub fn fetch_questions(
-        &mut self,
+    pub async fn fetch_questions(
+        &self,
         survey_id: usize,
         language: Option<String>,
-    ) -> anyhow::Result<Vec<Question>> {
-        self.rate_limit();
-        let response = self
-            .inner
-            .get(format!(
-                "https://api.surveyhero.com/v1/surveys/{}/questions{}",
+    ) -> Result<Vec<Question>> {
+        let elements = self
+            .get_all::<Elements>(
+                self.url(&format!(
+                    "/surveys/{}/questions{}",
+                    survey_id,
+                    language.map(|l| format!("?lang={l}")).unwrap_or_default()
+                )),
+                "questions",
+            )
+            .await?;
+        Ok(elements
+            .into_iter()
+            .filter_map(Element::into_question)
+            .collect())
+    }
+
+    /// Fetches the skip logic of the survey.
+    pub async fn fetch_conditions(&self, survey_id: usize) -> Result<Vec<Condition>> {
+        let conditions: Conditions = self
+            .get_json(
+                self.url(&format!("/surveys/{survey_id}/conditions")),
+                "conditions",
+            )
+            .await?;
+        Ok(conditions.conditions)
+    }
+
+    /// Fetches all elements of the survey in order: texts, page breaks and questions.
+    pub async fn fetch_elements(
+        &self,
+        survey_id: usize,
+        language: Option<String>,
+    ) -> Result<Vec<Element>> {
+        self.get_all::<Elements>(
+            self.url(&format!(
+                "/surveys/{}/elements{}",
                 survey_id,
                 language.map(|l| format!("?lang={l}")).unwrap_or_default()
-            ))
-            .basic_auth(&self.username, Some(&self.password))
-            .send()?
-            .error_for_status()?;
+            )),
+            "elements",
+        )
+        .await
+    }
 
-        let text = response.text()?;
-        let elements: Elements = serde_json::from_str(&text).map_err(|e| {
-            anyhow::anyhow!("Cannot deserialize questions from:\n{text}\nError:{e:?}")
-        })?;
-        Ok(elements.questions().collect())
+    /// Fetches all responses of the survey, following all pages.
+    pub async fn fetch_responses(&self, survey_id: usize) -> Result<Vec<Response>> {
+        self.get_all::<Responses>(
+            self.url(&format!("/surveys/{survey_id}/responses")),
+            "responses",
+        )
+        .await
     }
 
-    #[allow(dead_code)]
-    // TODO: Get all elements from survey
-    // filter "text" and "question" type
-    fn fetch_elements(
-        &mut self,
+    /// Creates a question at the end of the survey and returns its ID.
+    pub async fn create_question(
+        &self,
         survey_id: usize,
+        question: &QuestionPayload,
+    ) -> Result<u64> {
+        let response = self
+            .send(
+                self.inner
+                    .post(self.url(&format!("/surveys/{survey_id}/questions")))
+                    .json(question),
+            )
+            .await?;
+        let text = check_status(response, "creating a question")
+            .await?
+            .text()
+            .await?;
+        let created: CreatedQuestion = deserialize(text, "created question")?;
+        Ok(created.question.id())
+    }
+
+    /// Replaces the texts and answers of an existing question, in `language` if given and in the
+    /// default language otherwise.
+    pub async fn update_question(
+        &self,
+        survey_id: usize,
+        question_id: u64,
+        question: &QuestionPayload,
         language: Option<String>,
-    ) -> anyhow::Result<Vec<Element>> {
-        self.rate_limit();
+    ) -> Result<()> {
         let response = self
-            .inner
-            .get(format!(
-                "https://api.surveyhero.com/v1/surveys/{}/elements{}",
-                survey_id,
-                language.map(|l| format!("?lang={l}")).unwrap_or_default()
-            ))
-            .basic_auth(&self.username, Some(&self.password))
-            .send()?
-            .error_for_status()?;
+            .send(
+                self.inner
+                    .put(self.url(&format!(
+                        "/surveys/{}/questions/{}{}",
+                        survey_id,
+                        question_id,
+                        language.map(|l| format!("?lang={l}")).unwrap_or_default()
+                    )))
+                    .json(question),
+            )
+            .await?;
+        check_status(response, &format!("updating question {question_id}")).await?;
+        Ok(())
+    }
+
+    /// Fetches the links that let translators edit the texts of a language.
+    pub async fn fetch_translation_links(&self, survey_id: usize) -> Result<Vec<TranslationLink>> {
+        let links: TranslationLinks = self
+            .get_json(
+                self.url(&format!("/surveys/{survey_id}/translation_links")),
+                "translation links",
+            )
+            .await?;
+        Ok(links.translation_links)
+    }
+
+    /// Creates a link that lets translators edit the texts of `language`.
+    pub async fn create_translation_link(
+        &self,
+        survey_id: usize,
+        language: &str,
+    ) -> Result<TranslationLink> {
+        let response = self
+            .send(
+                self.inner
+                    .post(self.url(&format!("/surveys/{survey_id}/translation_links")))
+                    .json(&serde_json::json!({ "language": language })),
+            )
+            .await?;
+        let text = check_status(
+            response,
+            &format!("creating a translation link for {language}"),
+        )
+        .await?
+        .text()
+        .await?;
+        let created: CreatedTranslationLink = deserialize(text, "created translation link")?;
+        Ok(created.translation_link)
+    }
+
+    /// Removes access for a translation link.
+    pub async fn revoke_translation_link(&self, survey_id: usize, link_id: u64) -> Result<()> {
+        let response = self
+            .send(
+                self.inner
+                    .delete(self.url(&format!("/surveys/{survey_id}/translation_links/{link_id}"))),
+            )
+            .await?;
+        check_status(response, &format!("revoking translation link {link_id}")).await?;
+        Ok(())
+    }
+
+    /// Fetches `url` and deserializes the body, including the body in the error if that fails.
+    async fn get_json<T: DeserializeOwned>(&self, url: String, what: &str) -> Result<T> {
+        deserialize(self.get_text(url, what).await?, what)
+    }
 
-        let _elements: Elements = response.json()?;
+    async fn get_text(&self, url: String, what: &str) -> Result<String> {
+        let response = self.send(self.inner.get(url)).await?;
+        Ok(check_status(response, &format!("fetching {what}"))
+            .await?
+            .text()
+            .await?)
+    }
 
-        let elements = vec![Element::Text {
-            text: "TODO".to_string(),
-        }];
-        Ok(elements)
+    /// Fetches all items of a list endpoint, following the `page` parameter from 1. The first
+    /// page gives the page size, and the list ends with the first page shorter than that (or
+    /// empty), so only a list filling its last page exactly costs an extra request. An endpoint
+    /// that ignores the parameter answers with the same items again, which ends the list as well.
+    async fn get_all<P: Page>(&self, url: String, what: &str) -> Result<Vec<P::Item>> {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        let mut items = vec![];
+        let mut page_size = None;
+        let mut previous_ids = None;
+        for number in 1..=MAX_PAGES {
+            log::debug!("Downloading page {number} of {what}");
+            let page: P = self
+                .get_json(format!("{url}{separator}page={number}"), what)
+                .await?;
+            let ids = page.ids();
+            if ids.iter().any(Option::is_some) && previous_ids.as_ref() == Some(&ids) {
+                log::debug!("Page {number} of {what} repeats the previous one");
+                return Ok(items);
+            }
+            let page = page.into_items();
+            let length = page.len();
+            items.extend(page);
+            let page_size = *page_size.get_or_insert(length);
+            if length == 0 || length < page_size {
+                return Ok(items);
+            }
+            previous_ids = Some(ids);
+        }
+        Err(Error::TooManyPages {
+            what: what.to_owned(),
+            pages: MAX_PAGES,
+        })
     }
 
-    // Try to avoid 429 errors by rate limiting the client
-    fn rate_limit(&mut self) {
-        if let Some(last) = self.last_request {
-            let elapsed = last.elapsed().unwrap();
-            let limit = Duration::from_secs(1);
-            if elapsed < limit {
-                std::thread::sleep((limit - elapsed) + Duration::from_millis(100));
+    /// Sends a request, or answers it from the cassette when replaying. When recording, the
+    /// final response is saved to the cassette.
+    async fn send(&self, request: RequestBuilder) -> Result<HttpResponse> {
+        let request = request
+            .basic_auth(&self.username, Some(&self.password))
+            .build()?;
+        let Some(cassette) = &self.cassette else {
+            return self.send_with_retries(request).await;
+        };
+        let key = CassetteRequest {
+            method: request.method().to_string(),
+            path: request
+                .url()
+                .as_str()
+                .strip_prefix(&self.base_url)
+                .unwrap_or(request.url().as_str())
+                .to_owned(),
+            request_body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .and_then(|bytes| serde_json::from_slice(bytes).ok()),
+        };
+        if cassette.is_replay() {
+            return cassette.replay_request(&key);
+        }
+        let response = self.send_with_retries(request).await?;
+        cassette.record_response(key, response).await
+    }
+
+    /// Sends an authenticated request once the rate limiter allows it. Failed attempts are
+    /// repeated according to the [`RetryPolicy`]; the last response (or error) is returned.
+    async fn send_with_retries(&self, request: Request) -> Result<HttpResponse> {
+        let url = request.url().clone();
+        let method = request.method().clone();
+        let mut attempt = 1;
+        loop {
+            let Some(this_attempt) = request.try_clone() else {
+                // Streaming bodies cannot be sent twice
+                self.limiter.acquire().await;
+                return Ok(self.inner.execute(request).await?);
+            };
+            self.limiter.acquire().await;
+            let result = self.inner.execute(this_attempt).await;
+            let (reason, retry_after) = match &result {
+                Ok(response) if RetryPolicy::is_retryable(&method, response.status()) => (
+                    format!("HTTP status {}", response.status()),
+                    retry_after(response.headers()),
+                ),
+                // The request may have arrived before the timeout
+                Err(error) if error.is_timeout() && is_idempotent(&method) => {
+                    ("timeout".to_owned(), None)
+                }
+                // The request was never sent
+                Err(error) if error.is_connect() => ("connection error".to_owned(), None),
+                _ => return Ok(result?),
+            };
+            if attempt >= self.retry.max_attempts {
+                log::warn!("Giving up on {url} after {attempt} attempt(s): {reason}");
+                return Ok(result?);
             }
+            let delay = self.retry.delay(attempt, retry_after);
+            log::warn!(
+                "Attempt {attempt}/{} for {url} failed ({reason}), retrying in {delay:.1?}{}",
+                self.retry.max_attempts,
+                if retry_after.is_some() {
+                    " as requested by Retry-After"
+                } else {
+                    ""
+                }
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
-        self.last_request = Some(SystemTime::now());
     }
 }
 
@@ -131,8 +510,48 @@ struct Languages {
 #[derive(Debug, Deserialize)]
 pub struct Language {
     pub code: String,
-    is_default: bool,
-    is_active: bool,
+    pub is_default: bool,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Conditions {
+    conditions: Vec<Condition>,
+}
+
+/// A skip logic rule: after `question_id` is answered (with `choice_id`, if set), the survey
+/// continues as described by `action` instead of with the next question.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Condition {
+    pub question_id: u64,
+    #[serde(default)]
+    pub choice_id: Option<u64>,
+    pub action: ConditionAction,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum ConditionAction {
+    #[serde(rename = "jump_to_question")]
+    JumpToQuestion { question_id: u64 },
+    #[serde(rename = "end_survey")]
+    EndSurvey,
+    #[serde(other)]
+    Unknown,
+}
+
+/// The most pages fetched from one list endpoint, a safeguard against endpoints that never
+/// return a last page.
+const MAX_PAGES: usize = 1000;
+
+/// A page of a list endpoint, see `AsyncClient::get_all`.
+trait Page: DeserializeOwned {
+    type Item;
+
+    /// The IDs of the items, `None` for items without one.
+    fn ids(&self) -> Vec<Option<u64>>;
+
+    fn into_items(self) -> Vec<Self::Item>;
 }
 
 #[derive(Debug, Deserialize)]
@@ -140,82 +559,226 @@ pub struct Elements {
     elements: Vec<Element>,
 }
 
+impl Page for Elements {
+    type Item = Element;
+
+    fn ids(&self) -> Vec<Option<u64>> {
+        self.elements
+            .iter()
+            .map(|e| e.question().map(Question::id))
+            .collect()
+    }
+
+    fn into_items(self) -> Vec<Element> {
+        self.elements
+    }
+}
+
 impl Elements {
     pub fn questions(self) -> impl Iterator<Item = Question> {
-        self.elements.into_iter().filter_map(|e| {
-            if let Element::Question { question, .. } = e {
-                Some(question)
-            } else {
-                None
-            }
-        })
+        self.elements.into_iter().filter_map(Element::into_question)
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(tag = "type")]
 pub enum Element {
+    /// A block of (HTML) text shown between questions, e.g. the survey introduction.
     #[serde(rename = "text")]
     Text { text: String },
+    /// Starts a new page of the survey.
+    #[serde(rename = "page_break")]
+    PageBreak,
     #[serde(rename = "question")]
     Question { question: Question },
+    /// Elements without meaning for the Markdown files, e.g. images.
     #[serde(other)]
     Unknown,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type")]
+impl Element {
+    pub fn question(&self) -> Option<&Question> {
+        match self {
+            Self::Question { question } => Some(question),
+            _ => None,
+        }
+    }
+
+    pub fn into_question(self) -> Option<Question> {
+        match self {
+            Self::Question { question } => Some(question),
+            _ => None,
+        }
+    }
+}
+
+/// A survey question. Fields the client does not interpret, e.g. whether an answer is required,
+/// are kept in `raw` as sent by SurveyHero.
+///
+/// Questions of the supported types have to match their variant, only questions of other types
+/// become [`Question::Unknown`] (see the [`Deserialize`] implementation).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", remote = "Self")]
 pub enum Question {
     #[serde(rename = "choice_list")]
     ChoiceList {
+        question_id: u64,
         question_text: String,
         description_text: String,
         choice_list: ChoiceList,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
     },
     #[serde(rename = "input")]
     Input {
+        question_id: u64,
         question_text: String,
         description_text: String,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
     },
     #[serde(rename = "choice_table")]
     ChoiceTable {
+        question_id: u64,
         question_text: String,
         description_text: String,
         choice_table: ChoiceTable,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
     },
     #[serde(rename = "rating_scale")]
     RatingScale {
+        question_id: u64,
         question_text: String,
         description_text: String,
+        /// All other fields of the question, including the scale and its labels.
+        #[serde(flatten)]
+        raw: Map<String, Value>,
     },
     #[serde(rename = "ranking")]
     Ranking {
+        question_id: u64,
         question_text: String,
         description_text: String,
         ranking: RankingChoices,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
     },
     #[serde(rename = "input_list")]
     InputList {
+        question_id: u64,
         question_text: String,
         description_text: String,
         input_list: InputListInputs,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
+    },
+    /// A question type the client does not support (e.g. a slider, date or file upload
+    /// question). Only its texts are compared with the Markdown files.
+    #[serde(untagged, skip_deserializing)]
+    Unknown {
+        /// The `type` sent by SurveyHero.
+        #[serde(rename = "type")]
+        kind: String,
+        question_id: u64,
+        question_text: String,
+        #[serde(default)]
+        description_text: String,
+        /// All other fields of the question, as sent by SurveyHero.
+        #[serde(flatten)]
+        raw: Map<String, Value>,
     },
 }
 
+/// The `type`s of the questions that have their own [`Question`] variant.
+const SUPPORTED_QUESTION_TYPES: &[&str] = &[
+    "choice_list",
+    "input",
+    "choice_table",
+    "rating_scale",
+    "ranking",
+    "input_list",
+];
+
+/// The fields of a [`Question::Unknown`].
+#[derive(Deserialize)]
+struct UnknownQuestion {
+    #[serde(rename = "type")]
+    kind: String,
+    question_id: u64,
+    question_text: String,
+    #[serde(default)]
+    description_text: String,
+    #[serde(flatten)]
+    raw: Map<String, Value>,
+}
+
+impl<'de> Deserialize<'de> for Question {
+    /// Reads the `type` first, so that a malformed question of a supported type is an error
+    /// instead of an unsupported question.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let kind = value
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| D::Error::missing_field("type"))?;
+        if SUPPORTED_QUESTION_TYPES.contains(&kind) {
+            return Question::deserialize(value).map_err(D::Error::custom);
+        }
+        let question = UnknownQuestion::deserialize(value).map_err(D::Error::custom)?;
+        Ok(Self::Unknown {
+            kind: question.kind,
+            question_id: question.question_id,
+            question_text: question.question_text,
+            description_text: question.description_text,
+            raw: question.raw,
+        })
+    }
+}
+
+impl Serialize for Question {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        Question::serialize(self, serializer)
+    }
+}
+
 impl Question {
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::ChoiceList { question_id, .. } => *question_id,
+            Self::Input { question_id, .. } => *question_id,
+            Self::ChoiceTable { question_id, .. } => *question_id,
+            Self::RatingScale { question_id, .. } => *question_id,
+            Self::Ranking { question_id, .. } => *question_id,
+            Self::InputList { question_id, .. } => *question_id,
+            Self::Unknown { question_id, .. } => *question_id,
+        }
+    }
+
+    /// The question text converted to Markdown.
     pub fn text(&self) -> String {
-        normalize_surveyhero_text(match self {
+        Normalizer::markdown().normalize(self.raw_text())
+    }
+
+    /// The question text as stored on SurveyHero.
+    pub fn raw_text(&self) -> &str {
+        match self {
             Self::ChoiceList { question_text, .. } => question_text,
             Self::Input { question_text, .. } => question_text,
             Self::ChoiceTable { question_text, .. } => question_text,
             Self::RatingScale { question_text, .. } => question_text,
             Self::Ranking { question_text, .. } => question_text,
             Self::InputList { question_text, .. } => question_text,
-        })
+            Self::Unknown { question_text, .. } => question_text,
+        }
     }
 
     pub fn description_text(&self) -> String {
-        normalize_surveyhero_text(match self {
+        Normalizer::markdown().normalize(self.raw_description_text())
+    }
+
+    pub fn raw_description_text(&self) -> &str {
+        match self {
             Self::ChoiceList {
                 description_text, ..
             } => description_text,
@@ -234,7 +797,120 @@ impl Question {
             Self::InputList {
                 description_text, ..
             } => description_text,
-        })
+            Self::Unknown {
+                description_text, ..
+            } => description_text,
+        }
+    }
+
+    /// Every translatable text of the question, as stored on SurveyHero.
+    pub fn texts(&self) -> Vec<(TextKind, &str)> {
+        let mut texts = vec![
+            (TextKind::Question, self.raw_text()),
+            (TextKind::Description, self.raw_description_text()),
+        ];
+        match self {
+            Self::ChoiceList { choice_list, .. } => texts.extend(
+                choice_list
+                    .choices
+                    .iter()
+                    .map(|c| (TextKind::Answer, c.label.as_str())),
+            ),
+            Self::ChoiceTable { choice_table, .. } => {
+                texts.extend(
+                    choice_table
+                        .rows
+                        .iter()
+                        .map(|r| (TextKind::Row, r.label.as_str())),
+                );
+                texts.extend(
+                    choice_table
+                        .choices
+                        .iter()
+                        .map(|c| (TextKind::Column, c.label.as_str())),
+                );
+            }
+            Self::Ranking { ranking, .. } => texts.extend(
+                ranking
+                    .choices
+                    .iter()
+                    .map(|c| (TextKind::Answer, c.label.as_str())),
+            ),
+            Self::InputList { input_list, .. } => texts.extend(
+                input_list
+                    .inputs
+                    .iter()
+                    .map(|c| (TextKind::Answer, c.label.as_str())),
+            ),
+            Self::Input { .. } | Self::RatingScale { .. } | Self::Unknown { .. } => {}
+        }
+        texts
+    }
+
+    /// Position of the choice with the given ID among the answers of a choice list.
+    pub fn choice_index(&self, choice_id: u64) -> Option<usize> {
+        match self {
+            Self::ChoiceList { choice_list, .. } => choice_list
+                .choices
+                .iter()
+                .position(|c| c.choice_id == choice_id),
+            _ => None,
+        }
+    }
+
+    /// IDs of the answers, rows or columns of the question, in the order of [`Question::texts`].
+    /// `None` where SurveyHero sends no ID.
+    pub fn ids(&self, kind: TextKind) -> Vec<Option<u64>> {
+        self.label_fields(kind)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// IDs and all other fields of the answers, rows or columns of the question, in the order of
+    /// [`Question::texts`]. The ID is `None` where SurveyHero sends none.
+    pub fn label_fields(&self, kind: TextKind) -> Vec<(Option<u64>, &Map<String, Value>)> {
+        match (self, kind) {
+            (Self::ChoiceList { choice_list, .. }, TextKind::Answer) => choice_list
+                .choices
+                .iter()
+                .map(|c| (Some(c.choice_id), &c.raw))
+                .collect(),
+            (Self::ChoiceTable { choice_table, .. }, TextKind::Row) => choice_table
+                .rows
+                .iter()
+                .map(|r| (r.row_id, &r.raw))
+                .collect(),
+            (Self::ChoiceTable { choice_table, .. }, TextKind::Column) => choice_table
+                .choices
+                .iter()
+                .map(|c| (Some(c.choice_id), &c.raw))
+                .collect(),
+            (Self::Ranking { ranking, .. }, TextKind::Answer) => ranking
+                .choices
+                .iter()
+                .map(|c| (c.choice_id, &c.raw))
+                .collect(),
+            (Self::InputList { input_list, .. }, TextKind::Answer) => input_list
+                .inputs
+                .iter()
+                .map(|c| (c.choice_id, &c.raw))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// The fields of the question the client does not interpret.
+    pub fn raw(&self) -> &Map<String, Value> {
+        match self {
+            Self::ChoiceList { raw, .. }
+            | Self::Input { raw, .. }
+            | Self::ChoiceTable { raw, .. }
+            | Self::RatingScale { raw, .. }
+            | Self::Ranking { raw, .. }
+            | Self::InputList { raw, .. }
+            | Self::Unknown { raw, .. } => raw,
+        }
     }
 
     pub fn is_free_form(&self) -> bool {
@@ -257,117 +933,183 @@ impl Question {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// The role of a text returned by [`Question::texts`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextKind {
+    Question,
+    Description,
+    Answer,
+    Row,
+    Column,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ChoiceList {
-    choices: Vec<Choice>,
+    pub choices: Vec<Choice>,
     pub settings: Settings,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
 impl ChoiceList {
     pub fn as_strs(&self) -> impl Iterator<Item = String> + '_ {
         self.choices
             .iter()
-            .map(|c| normalize_surveyhero_text(c.label.as_str()))
+            .map(|c| Normalizer::markdown().normalize(&c.label))
     }
 
-    pub fn mismatched_answers<'a>(&'a self, answers: &'a [&str]) -> Vec<(String, &'a str)> {
-        self.as_strs()
-            .zip(answers.iter().map(|s| normalize_markdown_text(s)))
-            .filter(|(s1, s2)| s1 != s2)
-            .collect()
+    pub fn mismatched_answers<'a>(
+        &self,
+        answers: &[&'a str],
+        normalizer: &Normalizer,
+    ) -> Vec<(String, &'a str)> {
+        mismatched(
+            self.choices.iter().map(|c| c.label.as_str()),
+            answers,
+            normalizer,
+        )
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ChoiceTable {
-    rows: Vec<Row>,
-    choices: Vec<Choice>,
+    pub rows: Vec<Row>,
+    /// The columns.
+    pub choices: Vec<Choice>,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
 impl ChoiceTable {
     pub fn column_strs(&self) -> impl Iterator<Item = String> + '_ {
         self.choices
             .iter()
-            .map(|c| normalize_surveyhero_text(c.label.as_str()))
+            .map(|c| Normalizer::markdown().normalize(&c.label))
     }
 
     pub fn rows_strs(&self) -> impl Iterator<Item = String> + '_ {
         self.rows
             .iter()
-            .map(|c| normalize_surveyhero_text(c.label.as_str()))
+            .map(|c| Normalizer::markdown().normalize(&c.label))
     }
 
-    pub fn mismatched_rows<'a>(&'a self, labels: &[&'a str]) -> Vec<(String, &'a str)> {
-        self.rows_strs()
-            .zip(labels.iter().map(|s| normalize_markdown_text(s)))
-            .filter(|(s1, s2)| s1 != s2)
-            .collect()
+    pub fn mismatched_rows<'a>(
+        &self,
+        labels: &[&'a str],
+        normalizer: &Normalizer,
+    ) -> Vec<(String, &'a str)> {
+        mismatched(
+            self.rows.iter().map(|r| r.label.as_str()),
+            labels,
+            normalizer,
+        )
     }
 
-    pub fn mismatched_columns<'a>(&'a self, choices: &'a [&str]) -> Vec<(String, &'a str)> {
-        self.column_strs()
-            .zip(choices.iter().map(|s| normalize_markdown_text(s)))
-            .filter(|(s1, s2)| s1 != s2)
-            .collect()
+    pub fn mismatched_columns<'a>(
+        &self,
+        choices: &[&'a str],
+        normalizer: &Normalizer,
+    ) -> Vec<(String, &'a str)> {
+        mismatched(
+            self.choices.iter().map(|c| c.label.as_str()),
+            choices,
+            normalizer,
+        )
     }
 }
-#[derive(Debug, Deserialize)]
+
+/// A row of a matrix. The answers to a matrix refer to it by [`MatrixCell::row_id`].
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Row {
-    label: String,
+    /// Missing in snapshots taken before the ID was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_id: Option<u64>,
+    pub label: String,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
-#[derive(Debug, Deserialize)]
+/// An answer of a choice list or a column of a matrix.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Choice {
-    label: String,
+    pub choice_id: u64,
+    pub label: String,
+    /// All other fields of the choice, e.g. whether it is an open response.
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Settings {
-    allows_multiple_choices: bool,
+    pub allows_multiple_choices: bool,
+    /// All other settings, e.g. the order of the choices.
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RankingChoices {
-    choices: Vec<ChoiceWithLabel>,
+    pub choices: Vec<ChoiceWithLabel>,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
 impl RankingChoices {
     pub fn as_strs(&self) -> impl Iterator<Item = String> + '_ {
         self.choices
             .iter()
-            .map(|c| normalize_surveyhero_text(c.label.as_str()))
+            .map(|c| Normalizer::markdown().normalize(&c.label))
     }
 
-    pub fn mismatched_answers<'a>(&'a self, answers: &'a [&str]) -> Vec<(String, &'a str)> {
-        self.as_strs()
-            .zip(answers.iter().map(|s| normalize_markdown_text(s)))
-            .filter(|(s1, s2)| s1 != s2)
-            .collect()
+    pub fn mismatched_answers<'a>(
+        &self,
+        answers: &[&'a str],
+        normalizer: &Normalizer,
+    ) -> Vec<(String, &'a str)> {
+        mismatched(
+            self.choices.iter().map(|c| c.label.as_str()),
+            answers,
+            normalizer,
+        )
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ChoiceWithLabel {
-    label: String,
+    /// The ID that the answers to a ranking refer to. Missing for the inputs of an input list
+    /// and in snapshots taken before the ID was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub choice_id: Option<u64>,
+    pub label: String,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct InputListInputs {
-    inputs: Vec<ChoiceWithLabel>,
+    pub inputs: Vec<ChoiceWithLabel>,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
 impl InputListInputs {
     pub fn as_strs(&self) -> impl Iterator<Item = String> + '_ {
         self.inputs
             .iter()
-            .map(|c| normalize_surveyhero_text(c.label.as_str()))
+            .map(|c| Normalizer::markdown().normalize(&c.label))
     }
 
-    pub fn mismatched_answers<'a>(&'a self, answers: &'a [&str]) -> Vec<(String, &'a str)> {
-        self.as_strs()
-            .zip(answers.iter().map(|s| normalize_markdown_text(s)))
-            .filter(|(s1, s2)| s1 != s2)
-            .collect()
+    pub fn mismatched_answers<'a>(
+        &self,
+        answers: &[&'a str],
+        normalizer: &Normalizer,
+    ) -> Vec<(String, &'a str)> {
+        mismatched(
+            self.inputs.iter().map(|c| c.label.as_str()),
+            answers,
+            normalizer,
+        )
     }
 }
 
@@ -376,45 +1118,426 @@ pub struct Surveys {
     pub surveys: Vec<Survey>,
 }
 
+impl Page for Surveys {
+    type Item = Survey;
+
+    fn ids(&self) -> Vec<Option<u64>> {
+        self.surveys
+            .iter()
+            .map(|s| Some(s.survey_id as u64))
+            .collect()
+    }
+
+    fn into_items(self) -> Vec<Survey> {
+        self.surveys
+    }
+}
+
+/// A survey in the list of all surveys. The list may include the metadata of the surveys.
 #[derive(Debug, Deserialize)]
 pub struct Survey {
     pub survey_id: usize,
     pub title: String,
+    #[serde(default)]
+    pub status: Option<SurveyStatus>,
+    #[serde(default)]
+    pub created_on: Option<String>,
+    #[serde(default)]
+    pub last_updated_on: Option<String>,
+    #[serde(default)]
+    pub number_of_responses: Option<u64>,
+}
+
+impl Survey {
+    /// The metadata of the survey, if the list includes its status.
+    pub fn details(&self) -> Option<SurveyDetails> {
+        Some(SurveyDetails {
+            survey_id: self.survey_id,
+            title: self.title.clone(),
+            status: self.status?,
+            created_on: self.created_on.clone(),
+            last_updated_on: self.last_updated_on.clone(),
+            number_of_responses: self.number_of_responses,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SurveyWrapper {
+    survey: SurveyDetails,
+}
+
+/// The metadata of a survey.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SurveyDetails {
+    pub survey_id: usize,
+    pub title: String,
+    pub status: SurveyStatus,
+    #[serde(default)]
+    pub created_on: Option<String>,
+    #[serde(default)]
+    pub last_updated_on: Option<String>,
+    #[serde(default)]
+    pub number_of_responses: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SurveyStatus {
+    Draft,
+    Open,
+    Closed,
+    #[serde(other)]
+    Unknown,
+}
+
+impl std::fmt::Display for SurveyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Self::Draft => "draft",
+            Self::Open => "open",
+            Self::Closed => "closed",
+            Self::Unknown => "unknown",
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Responses {
+    responses: Vec<Response>,
+}
+
+impl Page for Responses {
+    type Item = Response;
+
+    fn ids(&self) -> Vec<Option<u64>> {
+        self.responses.iter().map(|r| Some(r.response_id)).collect()
+    }
+
+    fn into_items(self) -> Vec<Response> {
+        self.responses
+    }
+}
+
+/// A single participant's responses.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Response {
+    pub response_id: u64,
+    /// E.g. `completed` or `in_progress`.
+    pub status: String,
+    /// Code of the language the survey was answered in.
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub date_started: Option<String>,
+    #[serde(default)]
+    pub date_completed: Option<String>,
+    pub answers: Vec<Answer>,
+}
+
+/// The answer to one question, depending on the kind of the question.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum Answer {
+    #[serde(rename = "choice_list")]
+    ChoiceList {
+        question_id: u64,
+        choices: Vec<AnswerChoice>,
+    },
+    #[serde(rename = "choice_table")]
+    ChoiceTable {
+        question_id: u64,
+        choice_table: Vec<MatrixCell>,
+    },
+    #[serde(rename = "input")]
+    Input { question_id: u64, text: String },
+    #[serde(rename = "rating_scale")]
+    RatingScale { question_id: u64, value: f64 },
+    /// Choice IDs from the highest to the lowest rank.
+    #[serde(rename = "ranking")]
+    Ranking { question_id: u64, ranking: Vec<u64> },
+    #[serde(rename = "input_list")]
+    InputList {
+        question_id: u64,
+        inputs: Vec<String>,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+impl Answer {
+    pub fn question_id(&self) -> Option<u64> {
+        match self {
+            Self::ChoiceList { question_id, .. }
+            | Self::ChoiceTable { question_id, .. }
+            | Self::Input { question_id, .. }
+            | Self::RatingScale { question_id, .. }
+            | Self::Ranking { question_id, .. }
+            | Self::InputList { question_id, .. } => Some(*question_id),
+            Self::Unknown => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AnswerChoice {
+    pub choice_id: u64,
+    /// Free text entered for an "open response" choice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// The column chosen in one row of a matrix.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MatrixCell {
+    pub row_id: u64,
+    pub choice_id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedQuestion {
+    question: Question,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranslationLinks {
+    translation_links: Vec<TranslationLink>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedTranslationLink {
+    translation_link: TranslationLink,
+}
+
+/// A link that lets translators without a SurveyHero account edit the texts of one language.
+/// Anyone with the link can change the survey, so access has to be removed before the survey is
+/// opened.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TranslationLink {
+    pub link_id: u64,
+    /// Code of the language, e.g. `de`.
+    pub language: String,
+    pub url: String,
+    pub is_active: bool,
+    #[serde(default)]
+    pub created_on: Option<String>,
+}
+
+/// A question as sent to SurveyHero when creating or updating it. The Markdown files contain
+/// neither the description nor most settings, so updates send the values of the existing
+/// question back in `description_text` and the `raw` fields.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum QuestionPayload {
+    #[serde(rename = "choice_list")]
+    ChoiceList {
+        question_text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description_text: Option<String>,
+        choice_list: ChoiceListPayload,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
+    },
+    #[serde(rename = "input")]
+    Input {
+        question_text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description_text: Option<String>,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
+    },
+    #[serde(rename = "choice_table")]
+    ChoiceTable {
+        question_text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description_text: Option<String>,
+        choice_table: ChoiceTablePayload,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
+    },
+    #[serde(rename = "ranking")]
+    Ranking {
+        question_text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description_text: Option<String>,
+        ranking: RankingPayload,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
+    },
+    #[serde(rename = "input_list")]
+    InputList {
+        question_text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description_text: Option<String>,
+        input_list: InputListPayload,
+        #[serde(flatten)]
+        raw: Map<String, Value>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChoiceListPayload {
+    pub choices: Vec<LabelPayload>,
+    pub settings: SettingsPayload,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsPayload {
+    pub allows_multiple_choices: bool,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChoiceTablePayload {
+    pub rows: Vec<LabelPayload>,
+    pub choices: Vec<LabelPayload>,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RankingPayload {
+    pub choices: Vec<LabelPayload>,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InputListPayload {
+    pub inputs: Vec<LabelPayload>,
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
+}
+
+/// An answer, row or column. Existing choices and rows are identified by their ID, so that
+/// updating their label keeps the responses and conditions attached to them.
+#[derive(Debug, Clone, Serialize)]
+pub struct LabelPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub choice_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row_id: Option<u64>,
+    pub label: String,
+    /// The other fields of an existing answer, e.g. whether it is an open response.
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
+}
+
+/// Turns an unsuccessful response into an error that includes the response body, which
+/// explains what SurveyHero rejected.
+async fn check_status(response: HttpResponse, action: &str) -> Result<HttpResponse> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let retry_after = retry_after(response.headers());
+    let body = response.text().await?;
+    let action = action.to_owned();
+    Err(match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::Unauthorized {
+            status,
+            action,
+            body,
+        },
+        StatusCode::TOO_MANY_REQUESTS => Error::RateLimited {
+            action,
+            retry_after,
+            body,
+        },
+        _ => Error::Http {
+            status,
+            action,
+            body,
+        },
+    })
 }
 
-pub fn normalize_surveyhero_text(text: &str) -> String {
-    static LINK_REGEX: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r#"<a href="(?<link>.*?)".*?>(?<text>.*?)</a>"#).unwrap());
-    static ITALICS_REGEX: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"<em>(?<text>.*?)</em>").unwrap());
-    static BOLD_REGEX: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"<strong>(?<text>.*?)</strong>").unwrap());
-
-    let text = text
-        .replace("&quot;", "\"")
-        .replace("&amp;", "&")
-        .replace("&nbsp;", " ")
-        .replace("&lt;", "<")
-        .replace("&gt;", ">")
-        // Note: we leave manually inserted newlines
-        // Unwanted newlines should be fixed on SurveyHero
-        .replace("<br>", "\n")
-        .replace("\u{202f}", " ")
-        .replace("\u{200b}", "")
-        .replace("&#39;", "’");
-
-    // Replace <a href="$link" ...>$text</a> with [$text]($link)
-    let text = LINK_REGEX.replace_all(&text, "[$text]($link)");
-
-    // Replace <em>$text</em> with *$text* and
-    // Replace <strong>$text</strong> with **$text**
-    let text = ITALICS_REGEX.replace_all(&text, "*$text*").to_string();
-    BOLD_REGEX.replace_all(&text, "**$text**").to_string()
-}
-
-fn normalize_markdown_text(text: &str) -> &str {
-    // Remove (open response) from the end of the Markdown text
-    text.strip_suffix("(open response)")
-        .map(|t| t.trim_end())
-        .unwrap_or(text)
+/// Deserializes a response body, keeping the body in the error if that fails.
+fn deserialize<T: DeserializeOwned>(body: String, what: &str) -> Result<T> {
+    serde_json::from_str(&body).map_err(|source| Error::Deserialize {
+        what: what.to_owned(),
+        body,
+        source,
+    })
+}
+
+/// Pairs up SurveyHero labels with Markdown answers and returns the pairs that differ after
+/// normalization, as (SurveyHero label converted to Markdown, original Markdown answer).
+fn mismatched<'a, 'b>(
+    labels: impl Iterator<Item = &'b str>,
+    answers: &[&'a str],
+    normalizer: &Normalizer,
+) -> Vec<(String, &'a str)> {
+    labels
+        .zip(answers.iter().copied())
+        .filter(|(sh, md)| normalizer.normalize(sh) != normalizer.normalize(md))
+        .map(|(sh, md)| (normalizer.normalize(sh), md))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn choice_list() -> Value {
+        json!({
+            "question_id": 1,
+            "type": "choice_list",
+            "question_text": "Do you use Rust?",
+            "description_text": "",
+            "is_required": true,
+            "choice_list": {
+                "choices": [{ "choice_id": 11, "label": "Yes" }],
+                "settings": { "allows_multiple_choices": false },
+            },
+        })
+    }
+
+    #[test]
+    fn deserializes_supported_questions() {
+        let question: Question = serde_json::from_value(choice_list()).unwrap();
+        assert!(matches!(
+            question,
+            Question::ChoiceList { question_id: 1, .. }
+        ));
+        // Serializing keeps the `type` and the fields the client does not interpret
+        assert_eq!(serde_json::to_value(&question).unwrap(), choice_list());
+    }
+
+    #[test]
+    fn keeps_unsupported_questions() {
+        let slider = json!({
+            "question_id": 2,
+            "type": "slider",
+            "question_text": "How much?",
+            "slider": { "min": 0, "max": 10 },
+        });
+        let question: Question = serde_json::from_value(slider.clone()).unwrap();
+        assert!(matches!(&question, Question::Unknown { kind, .. } if kind == "slider"));
+        assert_eq!(question.text(), "How much?");
+
+        let mut expected = slider;
+        expected["description_text"] = "".into();
+        assert_eq!(serde_json::to_value(&question).unwrap(), expected);
+    }
+
+    #[test]
+    fn rejects_malformed_supported_questions() {
+        let mut question = choice_list();
+        question["choice_list"]["choices"] = json!("Yes, No");
+        let error = deserialize::<Question>(question.to_string(), "questions").unwrap_err();
+        assert!(matches!(error, Error::Deserialize { .. }), "{}", error);
+
+        let mut question = choice_list();
+        question.as_object_mut().unwrap().remove("description_text");
+        assert!(serde_json::from_value::<Question>(question).is_err());
+
+        let mut question = choice_list();
+        question.as_object_mut().unwrap().remove("type");
+        assert!(serde_json::from_value::<Question>(question).is_err());
+    }
 }
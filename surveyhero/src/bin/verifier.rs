@@ -1,102 +1,648 @@
 use anyhow::Context;
 use clap::Parser;
 use std::io::ErrorKind;
-use std::path::PathBuf;
-use surveyhero::api::Question;
-use surveyhero::cli::Args;
-use surveyhero::render::render_questions;
-use surveyhero::{cli::VerifierCmd, fetch_surveyhero_data, markdown, Comparison};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use surveyhero::api::{AsyncClient, Client, Question, SurveyStatus, TranslationLink};
+use surveyhero::cli::{
+    survey_path, Args, ReportArgs, ResponsesCmd, SharedArgs, SnapshotArgs, SnapshotCmd,
+    TranslationLinksCmd,
+};
+use surveyhero::completeness::translation_completeness;
+use surveyhero::config::SurveyConfig;
+use surveyhero::credentials::CredentialChain;
+use surveyhero::download::{download_file, DownloadOutcome};
+use surveyhero::push::PushPlan;
+use surveyhero::render::{
+    render_markdown, render_questions, render_survey, render_survey_markdown,
+};
+use surveyhero::report::Report;
+use surveyhero::responses::{unknown_answers, ResponseStore};
+use surveyhero::snapshot::{diff, Snapshot, SnapshotStore};
+use surveyhero::suppress::Suppressions;
+use surveyhero::webhook::WebhookReceiver;
+use surveyhero::{
+    async_client, cli::VerifierCmd, fetch_surveyhero_data, fetch_surveyhero_infos, markdown,
+    SurveyData,
+};
 
-fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<ExitCode> {
     env_logger::init();
 
     let args = Args::parse();
-    let online_data = fetch_surveyhero_data(args.cmd.shared())?;
-
-    let base_path = PathBuf::from(format!("../surveys/{}", args.cmd.shared().path));
-    let pairs = if base_path.is_dir() {
-        let mut pairs = vec![(base_path.join("questions.md"), online_data.main)];
-        for (language, questions) in online_data.secondary_languages {
-            pairs.push((
-                base_path
-                    .join("translations")
-                    .join(language)
-                    .with_extension("md"),
-                questions,
-            ));
-        }
-        pairs
-    } else {
-        vec![(base_path, online_data.main)]
-    };
-
+    let credentials = args.credentials.chain();
     match args.cmd {
-        VerifierCmd::Check { .. } => {
-            for (path, questions) in pairs {
-                println!("-----\nChecking {}\n", path.display());
-
-                let markdown = match std::fs::read_to_string(&path) {
-                    Ok(markdown) => markdown,
-                    Err(error) if error.kind() == ErrorKind::NotFound => {
-                        eprintln!(
-                            "{} not found, creating it with data from SurveyHero",
-                            path.display()
-                        );
-                        render_questions(&questions, &path)?;
-                        std::fs::read_to_string(&path)?
-                    }
-                    Err(e) => return Err(e.into()),
-                };
-                let markdown_questions = markdown::parse(&markdown)
-                    .with_context(|| format!("Cannot parse {} as Markdown", path.display()))?;
-                check_questions(&markdown_questions, &questions);
-            }
+        VerifierCmd::Check {
+            shared,
+            report,
+            snapshot,
+            emit_patch,
+        } => check(
+            &shared,
+            &credentials,
+            &report,
+            &snapshot,
+            emit_patch.as_deref(),
+        ),
+        VerifierCmd::Download { shared } => download(&shared, &credentials),
+        VerifierCmd::Push { shared, apply } => push(&shared, &credentials, apply),
+        VerifierCmd::PushTranslation {
+            shared,
+            lang,
+            apply,
+        } => push_translation(&shared, &credentials, &lang, apply),
+        VerifierCmd::Snapshot {
+            cmd:
+                SnapshotCmd::List {
+                    survey_id,
+                    snapshots,
+                },
+        } => list_snapshots(survey_id, &SnapshotStore::new(snapshots)),
+        VerifierCmd::Snapshot {
+            cmd:
+                SnapshotCmd::Diff {
+                    survey_id,
+                    snapshots,
+                    from,
+                    to,
+                },
+        } => diff_snapshots(survey_id, &SnapshotStore::new(snapshots), from.zip(to)),
+        VerifierCmd::Responses {
+            cmd: ResponsesCmd::Download { survey_id, store },
+        } => download_responses(survey_id, &credentials, &ResponseStore::new(store)),
+        VerifierCmd::Responses {
+            cmd:
+                ResponsesCmd::Listen {
+                    survey_id,
+                    address,
+                    store,
+                    secret,
+                },
+        } => listen_for_responses(survey_id, &address, ResponseStore::new(store), secret),
+        VerifierCmd::CheckTranslations { path, report } => {
+            check_translations(&survey_path(&path), &report)
         }
-        VerifierCmd::Download { .. } => {
-            for (path, questions) in pairs {
-                // Do not overwrite the English version, as it contains special metadata and
-                // comments
-                if path
-                    .file_name()
-                    .map(|p| p != "questions.md")
-                    .unwrap_or(true)
-                {
+        VerifierCmd::TranslationStatus {
+            survey_id,
+            details,
+            json,
+        } => translation_status(survey_id, &credentials, details, json),
+        VerifierCmd::TranslationLinks { cmd } => translation_links(cmd, &credentials),
+        VerifierCmd::Surveys { survey_id, json } => list_surveys(survey_id, &credentials, json),
+    }
+}
+
+fn check(
+    shared: &SharedArgs,
+    credentials: &CredentialChain,
+    report_args: &ReportArgs,
+    snapshot_args: &SnapshotArgs,
+    emit_patch: Option<&Path>,
+) -> anyhow::Result<ExitCode> {
+    let base_path = shared.survey_path();
+    let mut online_data = survey_data(shared, credentials, snapshot_args)?;
+    let conditions = std::mem::take(&mut online_data.conditions);
+    let elements = std::mem::take(&mut online_data.elements);
+    let pairs = local_files(&base_path, online_data);
+    let main_path = pairs[0].0.clone();
+    let config = SurveyConfig::load(&base_path)?;
+    let suppressions = Suppressions::load(&base_path)?;
+
+    let mut report = Report::default();
+    let mut sources = vec![];
+    for (path, questions) in pairs {
+        eprintln!("Checking {}", path.display());
+
+        let markdown = match std::fs::read_to_string(&path) {
+            Ok(markdown) => markdown,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                eprintln!(
+                    "{} not found, creating it with data from SurveyHero",
+                    path.display()
+                );
+                if path == main_path {
+                    render_survey(&elements, &path)?;
+                } else {
                     render_questions(&questions, &path)?;
                 }
+                std::fs::read_to_string(&path)?
             }
+            Err(e) => return Err(e.into()),
+        };
+        let markdown_questions = markdown::parse(&markdown)
+            .with_context(|| format!("Cannot parse {} as Markdown", path.display()))?;
+        report.check_file(
+            &path,
+            &markdown,
+            &markdown_questions,
+            &questions,
+            &config.normalize,
+        );
+        // Only the main file describes the survey flow and sections, translations follow it
+        // implicitly
+        if path == main_path {
+            report.check_flow(
+                &path,
+                &markdown,
+                &markdown_questions,
+                &questions,
+                &conditions,
+            );
+            report.check_layout(
+                &path,
+                &markdown,
+                &markdown_questions,
+                &elements,
+                &config.normalize,
+            );
         }
+        sources.push((path, markdown));
     }
 
-    Ok(())
+    report.apply_suppressions(&suppressions);
+
+    if let Some(patch_path) = emit_patch {
+        let patch = sources
+            .iter()
+            .filter_map(|(path, source)| report.patch(path, source))
+            .collect::<String>();
+        std::fs::write(patch_path, patch)
+            .with_context(|| format!("Cannot write {}", patch_path.display()))?;
+        eprintln!("Patch written to {}", patch_path.display());
+    }
+    write_report(&report, report_args)
 }
 
-fn check_questions(markdown_questions: &[markdown::Question], sh_questions: &[Question]) {
-    for (online, markdown) in markdown_questions.iter().zip(sh_questions.iter()) {
-        let comparison = online.compare(markdown);
-        if !matches!(comparison, Comparison::Equal) {
-            println!("Q: '{}'", online.text);
-            println!("  {:#?}", comparison);
+/// Loads the survey from the newest snapshot if `--offline` or `--max-age` allow it, and
+/// otherwise downloads it and saves a new snapshot.
+fn survey_data(
+    shared: &SharedArgs,
+    credentials: &CredentialChain,
+    args: &SnapshotArgs,
+) -> anyhow::Result<SurveyData> {
+    let store = SnapshotStore::new(&args.snapshots);
+    if args.offline || args.max_age.is_some() {
+        let snapshot = match args.max_age {
+            Some(max_age) => store.latest_within(shared.survey_id, max_age)?,
+            None => store.latest(shared.survey_id)?,
+        };
+        match snapshot {
+            Some(snapshot) => {
+                eprintln!(
+                    "Using the snapshot of survey {} taken {}s ago",
+                    shared.survey_id,
+                    snapshot.age().as_secs()
+                );
+                return Ok(snapshot.data);
+            }
+            None if args.offline => anyhow::bail!(
+                "no snapshot of survey {} in {}, run check without --offline first",
+                shared.survey_id,
+                args.snapshots.display()
+            ),
+            _ => {}
         }
     }
+    let data = fetch_surveyhero_data(&shared.client(credentials)?, shared.survey_id)?;
+    let snapshot = Snapshot::new(shared.survey_id, data);
+    let path = store.save(&snapshot)?;
+    log::info!("Snapshot saved to {}", path.display());
+    Ok(snapshot.data)
+}
 
-    if markdown_questions.len() > sh_questions.len() {
-        println!(
-            "Missing questions in the online version:\n{}",
-            markdown_questions[sh_questions.len()..]
+fn download(shared: &SharedArgs, credentials: &CredentialChain) -> anyhow::Result<ExitCode> {
+    let base_path = shared.survey_path();
+    let client = shared.client(credentials)?;
+    let mut online_data = fetch_surveyhero_data(&client, shared.survey_id)?;
+    let elements = std::mem::take(&mut online_data.elements);
+    let pairs = local_files(&base_path, online_data);
+    let main_path = pairs[0].0.clone();
+    let mut conflicts = false;
+    for (path, questions) in pairs {
+        // Do not overwrite the English version, as it contains special metadata and
+        // comments
+        if path
+            .file_name()
+            .map(|p| p != "questions.md")
+            .unwrap_or(true)
+        {
+            let theirs = if path == main_path {
+                render_survey_markdown(&elements)
+            } else {
+                render_markdown(&questions)
+            };
+            let outcome = download_file(&path, theirs)?;
+            eprintln!("{}: {outcome:?}", path.display());
+            conflicts |= outcome == DownloadOutcome::Conflicts;
+        }
+    }
+    if conflicts {
+        eprintln!("Some files contain conflicts between local and SurveyHero changes, please resolve them");
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn push(
+    shared: &SharedArgs,
+    credentials: &CredentialChain,
+    apply: bool,
+) -> anyhow::Result<ExitCode> {
+    let base_path = shared.survey_path();
+    let client = shared.client(credentials)?;
+    let config = SurveyConfig::load(&base_path)?;
+    let (path, questions) = local_files(
+        &base_path,
+        fetch_surveyhero_data(&client, shared.survey_id)?,
+    )
+    .into_iter()
+    .next()
+    .expect("the main file is always present");
+    let markdown = std::fs::read_to_string(&path)
+        .with_context(|| format!("Cannot read {}", path.display()))?;
+    let markdown_questions = markdown::parse(&markdown)
+        .with_context(|| format!("Cannot parse {} as Markdown", path.display()))?;
+
+    let plan = PushPlan::new(&markdown_questions, &questions, &config.normalize);
+    execute_plan(&plan, client, shared.survey_id, apply)
+}
+
+fn push_translation(
+    shared: &SharedArgs,
+    credentials: &CredentialChain,
+    lang: &str,
+    apply: bool,
+) -> anyhow::Result<ExitCode> {
+    let base_path = shared.survey_path();
+    let client = shared.client(credentials)?;
+    let config = SurveyConfig::load(&base_path)?;
+    let mut online_data = fetch_surveyhero_data(&client, shared.survey_id)?;
+    let Some(index) = online_data
+        .secondary_languages
+        .iter()
+        .position(|(language, _)| language == lang)
+    else {
+        anyhow::bail!(
+            "no active language '{lang}' in the survey. Available languages: {}",
+            online_data
+                .secondary_languages
                 .iter()
-                .map(|q| q.text)
+                .map(|(language, _)| language.as_str())
                 .collect::<Vec<_>>()
-                .join("\n-")
+                .join(", ")
+        );
+    };
+    let (language, questions) = online_data.secondary_languages.swap_remove(index);
+    let path = base_path
+        .join("translations")
+        .join(&language)
+        .with_extension("md");
+    let markdown = std::fs::read_to_string(&path)
+        .with_context(|| format!("Cannot read {}", path.display()))?;
+    let markdown_questions = markdown::parse(&markdown)
+        .with_context(|| format!("Cannot parse {} as Markdown", path.display()))?;
+
+    let plan = PushPlan::translation(
+        &language,
+        &markdown_questions,
+        &questions,
+        &config.normalize,
+    );
+    execute_plan(&plan, client, shared.survey_id, apply)
+}
+
+/// Prints a push plan and sends it to SurveyHero if `apply` is set.
+fn execute_plan(
+    plan: &PushPlan,
+    client: AsyncClient,
+    survey_id: usize,
+    apply: bool,
+) -> anyhow::Result<ExitCode> {
+    print!("{plan}");
+    if plan.is_blocked() {
+        eprintln!("Fix the blocking differences on SurveyHero, then run again");
+        return Ok(ExitCode::FAILURE);
+    }
+    if plan.is_empty() {
+        eprintln!("Nothing to push");
+    } else if apply {
+        plan.apply(&mut Client::from(client), survey_id)?;
+        eprintln!("Changes pushed to SurveyHero");
+    } else {
+        eprintln!("Run again with --apply to send these changes to SurveyHero");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn list_snapshots(survey_id: usize, store: &SnapshotStore) -> anyhow::Result<ExitCode> {
+    for path in store.list(survey_id)? {
+        println!("{}", path.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn diff_snapshots(
+    survey_id: usize,
+    store: &SnapshotStore,
+    paths: Option<(PathBuf, PathBuf)>,
+) -> anyhow::Result<ExitCode> {
+    let (from, to) = match paths {
+        Some(paths) => paths,
+        None => {
+            let mut snapshots = store.list(survey_id)?;
+            let (Some(to), Some(from)) = (snapshots.pop(), snapshots.pop()) else {
+                anyhow::bail!("survey {survey_id} needs at least two snapshots to compare");
+            };
+            (from, to)
+        }
+    };
+    let diff = diff(&Snapshot::load(&from)?, &Snapshot::load(&to)?);
+    if diff.is_empty() {
+        eprintln!("No changes between {} and {}", from.display(), to.display());
+    } else {
+        print!("{diff}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn download_responses(
+    survey_id: usize,
+    credentials: &CredentialChain,
+    store: &ResponseStore,
+) -> anyhow::Result<ExitCode> {
+    let mut responses = Client::from(async_client(credentials)?).fetch_responses(survey_id)?;
+    let unknown = unknown_answers(&responses);
+    if unknown > 0 {
+        log::warn!("{unknown} answer(s) have an unsupported question type and are not stored");
+        for response in &mut responses {
+            response.answers.retain(|a| a.question_id().is_some());
+        }
+    }
+    store.write_all(survey_id, &responses)?;
+    eprintln!(
+        "{} response(s) written to {}",
+        responses.len(),
+        store.path(survey_id).display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn listen_for_responses(
+    survey_id: usize,
+    address: &str,
+    store: ResponseStore,
+    secret: Option<String>,
+) -> anyhow::Result<ExitCode> {
+    let secret = secret
+        .or_else(|| std::env::var("SH_WEBHOOK_SECRET").ok())
+        .context("Pass the webhook secret with --secret or SH_WEBHOOK_SECRET")?;
+    let path = store.path(survey_id);
+    let receiver = WebhookReceiver::start(address, survey_id, secret, store)
+        .with_context(|| format!("Cannot listen on {address}"))?;
+    let totals = receiver.totals();
+    eprintln!(
+        "Listening on http://{}/webhook, appending to {} ({} response(s) so far)",
+        receiver.address(),
+        path.display(),
+        totals.responses
+    );
+    eprintln!("Running totals: http://{}/totals", receiver.address());
+    receiver.join();
+    anyhow::bail!("The webhook receiver stopped accepting connections")
+}
+
+fn check_translations(base_path: &Path, report_args: &ReportArgs) -> anyhow::Result<ExitCode> {
+    let english_path = base_path.join("questions.md");
+    let english = std::fs::read_to_string(&english_path)
+        .with_context(|| format!("Cannot read {}", english_path.display()))?;
+    let english_questions = markdown::parse(&english)
+        .with_context(|| format!("Cannot parse {} as Markdown", english_path.display()))?;
+
+    let mut translations = std::fs::read_dir(base_path.join("translations"))
+        .with_context(|| format!("Cannot list translations of {}", base_path.display()))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    translations.retain(|path| path.extension().is_some_and(|e| e == "md"));
+    translations.sort();
+
+    let mut report = Report::default();
+    for path in translations {
+        eprintln!("Checking {}", path.display());
+        let translation = std::fs::read_to_string(&path)?;
+        let translated_questions = markdown::parse(&translation)
+            .with_context(|| format!("Cannot parse {} as Markdown", path.display()))?;
+        report.check_translation(
+            &path,
+            &translation,
+            &translated_questions,
+            &english_questions,
         );
     }
-    if sh_questions.len() > markdown_questions.len() {
+    write_report(&report, report_args)
+}
+
+fn translation_status(
+    survey_id: usize,
+    credentials: &CredentialChain,
+    details: bool,
+    json: bool,
+) -> anyhow::Result<ExitCode> {
+    let languages = translation_completeness(&fetch_surveyhero_data(
+        &async_client(credentials)?,
+        survey_id,
+    )?);
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout().lock(), &languages)?;
+        println!();
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    for language in &languages {
         println!(
-            "Missing questions in the markdown version:\n-{}",
-            sh_questions[markdown_questions.len()..]
-                .iter()
-                .map(|q| q.text())
-                .collect::<Vec<_>>()
-                .join("\n-")
+            "{}: {:.1}% translated ({}/{})",
+            language.language,
+            language.percentage(),
+            language.translated,
+            language.total
         );
+        if details {
+            for text in &language.untranslated {
+                println!(
+                    "  [{}] {:?} {}: {:?}",
+                    text.question_id,
+                    text.kind,
+                    if text.empty {
+                        "missing"
+                    } else {
+                        "same as English"
+                    },
+                    text.text
+                );
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn translation_links(
+    cmd: TranslationLinksCmd,
+    credentials: &CredentialChain,
+) -> anyhow::Result<ExitCode> {
+    let mut client = Client::from(async_client(credentials)?);
+    let print = |link: &TranslationLink| {
+        println!(
+            "{:<6} {:<8} {}",
+            link.language,
+            if link.is_active { "active" } else { "revoked" },
+            link.url
+        )
+    };
+    match cmd {
+        TranslationLinksCmd::List { survey_id } => {
+            client
+                .fetch_translation_links(survey_id)?
+                .iter()
+                .for_each(print);
+        }
+        TranslationLinksCmd::Create {
+            survey_id,
+            languages,
+        } => {
+            let links = client.fetch_translation_links(survey_id)?;
+            for language in languages {
+                match links.iter().find(|l| l.is_active && l.language == language) {
+                    Some(link) => {
+                        eprintln!("{language} already has an active translation link");
+                        print(link);
+                    }
+                    None => print(&client.create_translation_link(survey_id, &language)?),
+                }
+            }
+            eprintln!("Remember to revoke the links before the survey starts.");
+        }
+        TranslationLinksCmd::Revoke {
+            survey_id,
+            languages,
+            all,
+        } => {
+            let links = client.fetch_translation_links(survey_id)?;
+            for language in &languages {
+                if !links.iter().any(|l| l.is_active && &l.language == language) {
+                    eprintln!("{language} has no active translation link");
+                }
+            }
+            for link in links
+                .iter()
+                .filter(|l| l.is_active && (all || languages.contains(&l.language)))
+            {
+                client.revoke_translation_link(survey_id, link.link_id)?;
+                eprintln!("Revoked the translation link of {}", link.language);
+            }
+        }
+        TranslationLinksCmd::Check { survey_id } => {
+            let survey = client.fetch_survey(survey_id)?;
+            let active = client
+                .fetch_translation_links(survey_id)?
+                .into_iter()
+                .filter(|l| l.is_active)
+                .collect::<Vec<_>>();
+            if active.is_empty() {
+                eprintln!("No active translation links");
+                return Ok(ExitCode::SUCCESS);
+            }
+            active.iter().for_each(print);
+            if survey.status == SurveyStatus::Open {
+                eprintln!(
+                    "The survey is open, but translators can still edit it. Revoke the links \
+                     with `translation-links revoke --survey-id {survey_id} --all`."
+                );
+                return Ok(ExitCode::FAILURE);
+            }
+            eprintln!("Revoke these links before the survey is opened.");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn list_surveys(
+    survey_id: Option<usize>,
+    credentials: &CredentialChain,
+    json: bool,
+) -> anyhow::Result<ExitCode> {
+    let surveys = fetch_surveyhero_infos(&async_client(credentials)?, survey_id)?;
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout().lock(), &surveys)?;
+        println!();
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let date = |date: &Option<String>| {
+        // Only the day of the ISO 8601 timestamps
+        date.as_deref()
+            .map(|d| d.chars().take(10).collect())
+            .unwrap_or_else(|| "-".to_owned())
+    };
+    for survey in &surveys {
+        let details = &survey.details;
+        let responses = details
+            .number_of_responses
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "?".to_owned());
+        if survey_id.is_some() {
+            println!("ID:        {}", details.survey_id);
+            println!("Title:     {}", details.title);
+            println!("Status:    {}", details.status);
+            println!("Languages: {}", survey.languages.join(", "));
+            println!("Responses: {responses}");
+            println!("Created:   {}", date(&details.created_on));
+            println!("Modified:  {}", date(&details.last_updated_on));
+        } else {
+            println!(
+                "{:<10} {:<7} {:>3} lang. {:>6} resp.  created {}  modified {}  {}",
+                details.survey_id,
+                details.status,
+                survey.languages.len(),
+                responses,
+                date(&details.created_on),
+                date(&details.last_updated_on),
+                details.title
+            );
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Pairs the local Markdown files of a survey with the matching SurveyHero questions.
+fn local_files(base_path: &Path, online_data: SurveyData) -> Vec<(PathBuf, Vec<Question>)> {
+    if base_path.is_dir() {
+        let mut pairs = vec![(base_path.join("questions.md"), online_data.main)];
+        for (language, questions) in online_data.secondary_languages {
+            pairs.push((
+                base_path
+                    .join("translations")
+                    .join(language)
+                    .with_extension("md"),
+                questions,
+            ));
+        }
+        pairs
+    } else {
+        vec![(base_path.to_owned(), online_data.main)]
+    }
+}
+
+fn write_report(report: &Report, args: &ReportArgs) -> anyhow::Result<ExitCode> {
+    match &args.output {
+        Some(output) => {
+            let mut file = std::fs::File::create(output)
+                .with_context(|| format!("Cannot create {}", output.display()))?;
+            report.write(args.format, &mut file)?;
+        }
+        None => report.write(args.format, &mut std::io::stdout().lock())?,
+    }
+    if report.has_drift() {
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
     }
 }
@@ -0,0 +1,293 @@
+use crate::error::{Error, Result};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Response as HttpResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Name of the cassette file in a survey directory.
+pub const CASSETTE_FILE: &str = "surveyhero.cassette.json";
+
+/// Recorded SurveyHero API traffic, so that a verifier run can be repeated without
+/// credentials or network access.
+///
+/// When recording, every final response (after retries) is saved together with its request.
+/// When replaying, requests are answered from the cassette and never reach the network;
+/// a request that was not recorded is an error. Requests are matched by method, path (relative
+/// to the base URL) and body, so the order of concurrent requests does not matter.
+///
+/// Credentials are never written to the cassette.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    #[serde(flatten)]
+    request: CassetteRequest,
+    status: u16,
+    /// The response body; a JSON value if it is valid JSON, otherwise a string.
+    body: Value,
+}
+
+/// The parts of a request that identify it in a cassette.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CassetteRequest {
+    pub method: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<Value>,
+}
+
+impl Cassette {
+    /// Starts an empty cassette, replacing the file at `path` with every recorded response.
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: Mode::Record,
+            interactions: Mutex::new(vec![]),
+        }
+    }
+
+    /// Loads the cassette at `path` for replaying.
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            Self::error(
+                &path,
+                format!("cannot read it ({e}), record it first with --record"),
+            )
+        })?;
+        let file: CassetteFile = serde_json::from_str(&text)
+            .map_err(|e| Self::error(&path, format!("cannot parse it: {e}")))?;
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            interactions: Mutex::new(file.interactions),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.mode == Mode::Replay
+    }
+
+    /// Answers `request` from the cassette. Every recorded response is used once, so repeated
+    /// identical requests get their responses in recorded order.
+    pub(crate) fn replay_request(&self, request: &CassetteRequest) -> Result<HttpResponse> {
+        let mut interactions = self.interactions.lock().unwrap();
+        let index = interactions
+            .iter()
+            .position(|i| &i.request == request)
+            .ok_or_else(|| {
+                Self::error(
+                    &self.path,
+                    format!(
+                        "no recorded response for {} {}, record it again with --record",
+                        request.method, request.path
+                    ),
+                )
+            })?;
+        let interaction = interactions.remove(index);
+        let body = match interaction.body {
+            Value::String(text) => text,
+            body => body.to_string(),
+        };
+        let response = http::Response::builder()
+            .status(interaction.status)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .map_err(|e| Self::error(&self.path, e.to_string()))?;
+        Ok(response.into())
+    }
+
+    /// Saves `response` as the answer to `request` and returns an equivalent response.
+    pub(crate) async fn record_response(
+        &self,
+        request: CassetteRequest,
+        response: HttpResponse,
+    ) -> Result<HttpResponse> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let text = response.text().await?;
+        let body = serde_json::from_str(&text).unwrap_or_else(|_| Value::String(text.clone()));
+
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push(Interaction {
+            request,
+            status: status.as_u16(),
+            body,
+        });
+        self.save(&interactions)?;
+
+        let mut builder = http::Response::builder().status(status);
+        if let Some(headers_mut) = builder.headers_mut() {
+            *headers_mut = headers;
+        }
+        builder
+            .body(text)
+            .map(HttpResponse::from)
+            .map_err(|e| Self::error(&self.path, e.to_string()))
+    }
+
+    /// Writes the cassette, sorted by path so that recordings of concurrent requests have a
+    /// stable order.
+    fn save(&self, interactions: &[Interaction]) -> Result<()> {
+        let mut file = CassetteFile {
+            interactions: interactions.to_vec(),
+        };
+        file.interactions
+            .sort_by(|a, b| a.request.path.cmp(&b.request.path));
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| Self::error(&self.path, e.to_string()))?;
+        std::fs::write(&self.path, json + "\n")
+            .map_err(|e| Self::error(&self.path, format!("cannot write it: {e}")))
+    }
+
+    fn error(path: &Path, message: String) -> Error {
+        Error::Cassette {
+            path: path.to_owned(),
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{AsyncClient, Client};
+    use crate::server::Server;
+    use crate::temp_dir::TempDir;
+    use serde_json::json;
+    use std::net::TcpListener;
+
+    fn request(method: &str, path: &str, body: Option<Value>) -> CassetteRequest {
+        CassetteRequest {
+            method: method.to_owned(),
+            path: path.to_owned(),
+            request_body: body,
+        }
+    }
+
+    fn replaying(interactions: Vec<Interaction>) -> Cassette {
+        Cassette {
+            path: PathBuf::from(CASSETTE_FILE),
+            mode: Mode::Replay,
+            interactions: Mutex::new(interactions),
+        }
+    }
+
+    fn interaction(request: CassetteRequest, body: Value) -> Interaction {
+        Interaction {
+            request,
+            status: 200,
+            body,
+        }
+    }
+
+    fn body(response: HttpResponse) -> Value {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(response.json()).unwrap()
+    }
+
+    #[test]
+    fn matches_method_path_and_body() {
+        let post = |name: &str| request("POST", "/surveys/1/questions", Some(json!({ "n": name })));
+        let cassette = replaying(vec![
+            interaction(post("a"), json!({ "id": 1 })),
+            interaction(request("GET", "/surveys", None), json!({ "id": 2 })),
+            interaction(post("b"), json!({ "id": 3 })),
+            interaction(post("a"), json!({ "id": 4 })),
+        ]);
+
+        let replay = |request| body(cassette.replay_request(&request).unwrap());
+        assert_eq!(replay(post("b")), json!({ "id": 3 }));
+        // Identical requests get their responses in recorded order
+        assert_eq!(replay(post("a")), json!({ "id": 1 }));
+        assert_eq!(replay(post("a")), json!({ "id": 4 }));
+        assert_eq!(replay(request("GET", "/surveys", None)), json!({ "id": 2 }));
+    }
+
+    #[test]
+    fn reports_missing_responses() {
+        let cassette = replaying(vec![interaction(
+            request("GET", "/surveys", None),
+            json!({}),
+        )]);
+        for request in [
+            request("POST", "/surveys", None),
+            request("GET", "/surveys?page=2", None),
+            request("GET", "/surveys", Some(json!({}))),
+        ] {
+            let error = cassette.replay_request(&request).unwrap_err();
+            assert!(
+                matches!(&error, Error::Cassette { message, .. } if message.contains("no recorded response")),
+                "{}",
+                error
+            );
+        }
+
+        let dir = TempDir::new("cassette");
+        let error = Cassette::replay(dir.join(CASSETTE_FILE)).unwrap_err();
+        assert!(error.to_string().contains("record it first"), "{}", error);
+    }
+
+    #[test]
+    fn records_and_replays_without_credentials() {
+        let dir = TempDir::new("cassette");
+        let path = dir.join(CASSETTE_FILE);
+        let server = Server::start(TcpListener::bind("127.0.0.1:0").unwrap(), "Test", |_| {
+            (200, json!({ "conditions": [] }))
+        })
+        .unwrap();
+
+        let mut client = Client::from(
+            AsyncClient::new("user".to_owned(), "secret-token".to_owned())
+                .with_base_url(format!("http://{}", server.address()))
+                .with_cassette(Cassette::record(&path)),
+        );
+        assert!(client.fetch_conditions(42).unwrap().is_empty());
+        drop(server);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("/surveys/42/conditions"), "{}", text);
+        assert!(!text.contains("secret-token"), "{}", text);
+        // The basic authentication header, base64 of "user:secret-token"
+        assert!(!text.contains("dXNlcjpzZWNyZXQtdG9rZW4"), "{}", text);
+
+        // Replaying needs neither credentials nor the server
+        let mut client = Client::from(
+            AsyncClient::new(String::new(), String::new())
+                .with_base_url("http://127.0.0.1:1")
+                .with_cassette(Cassette::replay(&path).unwrap()),
+        );
+        assert!(client.fetch_conditions(42).unwrap().is_empty());
+        assert!(matches!(
+            client.fetch_conditions(42),
+            Err(Error::Cassette { .. })
+        ));
+    }
+}
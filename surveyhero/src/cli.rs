@@ -1,8 +1,49 @@
+use crate::api::AsyncClient;
+use crate::cassette::{Cassette, CASSETTE_FILE};
+use crate::config::survey_file;
+use crate::credentials::{CredentialChain, CredentialSource};
+use crate::report::Format;
+use crate::{async_client, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 /// Verify the contents of the Annual Rust Survey on SurveyHero.
 #[derive(clap::Parser)]
 pub struct Args {
     #[clap(subcommand)]
     pub cmd: VerifierCmd,
+    #[clap(flatten)]
+    pub credentials: CredentialArgs,
+}
+
+/// Overrides of the SurveyHero API credentials. Without them, the credentials are taken from
+/// the environment, `.env`, the config file or the credential helper.
+#[derive(clap::Parser, Clone)]
+pub struct CredentialArgs {
+    /// Username of the SurveyHero API key. Prefer the other sources for the token, as command
+    /// lines are visible to other users.
+    #[clap(long, global = true, requires = "api_token")]
+    pub api_user: Option<String>,
+    /// Token of the SurveyHero API key.
+    #[clap(long, global = true, requires = "api_user")]
+    pub api_token: Option<String>,
+    /// Command printing `SH_API_USER=...` and `SH_API_TOKEN=...` lines, used if no other source
+    /// has credentials (instead of `SH_API_CREDENTIAL_HELPER`).
+    #[clap(long, global = true)]
+    pub credential_helper: Option<String>,
+}
+
+impl CredentialArgs {
+    pub fn chain(&self) -> CredentialChain {
+        let chain = CredentialChain::default().with_first(CredentialSource::Explicit {
+            username: self.api_user.clone(),
+            password: self.api_token.clone(),
+        });
+        match &self.credential_helper {
+            Some(command) => chain.with_helper(command.clone()),
+            None => chain,
+        }
+    }
 }
 
 #[derive(clap::Parser, Clone)]
@@ -13,6 +54,58 @@ pub struct SharedArgs {
     /// Survey path. Corresponds to a Markdown file or a directory relative to `../surveys/`.
     #[clap(long)]
     pub path: String,
+    /// Save the SurveyHero API traffic to `surveyhero.cassette.json` in the survey directory.
+    #[clap(long, conflicts_with = "replay")]
+    pub record: bool,
+    /// Answer the SurveyHero API requests from the recorded cassette instead of the network.
+    /// No credentials are needed.
+    #[clap(long)]
+    pub replay: bool,
+}
+
+impl SharedArgs {
+    pub fn survey_path(&self) -> PathBuf {
+        survey_path(&self.path)
+    }
+
+    /// Creates an API client for this survey: replaying from its cassette, or with the
+    /// credentials from `credentials` (and recording if requested).
+    pub fn client(&self, credentials: &CredentialChain) -> Result<AsyncClient> {
+        let cassette_path = survey_file(&self.survey_path(), CASSETTE_FILE);
+        if self.replay {
+            let cassette = Cassette::replay(cassette_path)?;
+            return Ok(AsyncClient::new(String::new(), String::new()).with_cassette(cassette));
+        }
+        let client = async_client(credentials)?;
+        if self.record {
+            return Ok(client.with_cassette(Cassette::record(cassette_path)));
+        }
+        Ok(client)
+    }
+}
+
+#[derive(clap::Parser, Clone)]
+pub struct ReportArgs {
+    /// Output format of the report.
+    #[clap(long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+    /// Write the report to this file instead of stdout.
+    #[clap(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Parser, Clone)]
+pub struct SnapshotArgs {
+    /// Use the newest snapshot instead of downloading the survey from SurveyHero.
+    #[clap(long, conflicts_with_all = ["max_age", "record", "replay"])]
+    pub offline: bool,
+    /// Use the newest snapshot if it is younger than this (e.g. `90s`, `30m`, `2h` or `1d`),
+    /// and download the survey otherwise.
+    #[clap(long, value_name = "AGE", value_parser = parse_duration)]
+    pub max_age: Option<Duration>,
+    /// Directory of the snapshot store. Every download is saved there.
+    #[clap(long, default_value = "snapshots")]
+    pub snapshots: PathBuf,
 }
 
 #[derive(clap::Parser, Clone)]
@@ -21,19 +114,232 @@ pub enum VerifierCmd {
     Check {
         #[clap(flatten)]
         shared: SharedArgs,
+        #[clap(flatten)]
+        report: ReportArgs,
+        #[clap(flatten)]
+        snapshot: SnapshotArgs,
+        /// Write a unified diff to this file that brings the local Markdown files in line with
+        /// SurveyHero (apply it with `git apply`).
+        #[clap(long, value_name = "FILE")]
+        emit_patch: Option<PathBuf>,
     },
-    /// Downloads all Markdown files from SurveyHero (overwrites without asking)
+    /// Downloads all Markdown files from SurveyHero, merging them with local changes made since
+    /// the last download.
     Download {
         #[clap(flatten)]
         shared: SharedArgs,
     },
+    /// Updates the questions on SurveyHero from the local English Markdown file. Without
+    /// `--apply`, only shows what would change.
+    Push {
+        #[clap(flatten)]
+        shared: SharedArgs,
+        /// Send the changes to SurveyHero.
+        #[clap(long)]
+        apply: bool,
+    },
+    /// Updates the texts of a secondary language on SurveyHero from the local translation in
+    /// `translations/<LANG>.md`. Without `--apply`, only shows what would change.
+    PushTranslation {
+        #[clap(flatten)]
+        shared: SharedArgs,
+        /// Language code of the translation, e.g. `de`.
+        #[clap(long)]
+        lang: String,
+        /// Send the changes to SurveyHero.
+        #[clap(long)]
+        apply: bool,
+    },
+    /// Works with the survey snapshots saved by `check`.
+    Snapshot {
+        #[clap(subcommand)]
+        cmd: SnapshotCmd,
+    },
+    /// Works with the individual survey responses.
+    Responses {
+        #[clap(subcommand)]
+        cmd: ResponsesCmd,
+    },
+    /// Checks that the translations have the same structure as the English questions, without
+    /// accessing SurveyHero.
+    CheckTranslations {
+        /// Survey directory relative to `../surveys/`, containing `questions.md` and
+        /// `translations/*.md`.
+        #[clap(long)]
+        path: String,
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+    /// Manages the links that let translators edit the texts of a language on SurveyHero.
+    TranslationLinks {
+        #[clap(subcommand)]
+        cmd: TranslationLinksCmd,
+    },
+    /// Lists the surveys of the account with their status, languages and number of responses,
+    /// or shows the details of one survey.
+    Surveys {
+        /// Only show this survey, with the codes of its languages.
+        #[clap(long)]
+        survey_id: Option<usize>,
+        /// Print the surveys as JSON.
+        #[clap(long)]
+        json: bool,
+    },
+    /// Shows how much of each secondary language has been translated on SurveyHero.
+    TranslationStatus {
+        /// ID of the survey.
+        #[clap(long)]
+        survey_id: usize,
+        /// List every untranslated text.
+        #[clap(long)]
+        details: bool,
+        /// Print the report as JSON.
+        #[clap(long)]
+        json: bool,
+    },
 }
 
-impl VerifierCmd {
-    pub fn shared(&self) -> &SharedArgs {
-        match self {
-            VerifierCmd::Check { shared } => shared,
-            VerifierCmd::Download { shared } => shared,
-        }
+#[derive(clap::Parser, Clone)]
+pub enum ResponsesCmd {
+    /// Downloads all responses of a survey to the local response store.
+    Download {
+        /// ID of the survey.
+        #[clap(long)]
+        survey_id: usize,
+        /// Directory of the response store. It contains personal data and must not be
+        /// committed.
+        #[clap(long, default_value = "responses")]
+        store: PathBuf,
+    },
+    /// Receives the webhook callbacks of SurveyHero for new responses and appends the
+    /// responses to the local response store.
+    Listen {
+        /// ID of the survey.
+        #[clap(long)]
+        survey_id: usize,
+        /// Address to listen on.
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        address: String,
+        /// Directory of the response store. It contains personal data and must not be
+        /// committed.
+        #[clap(long, default_value = "responses")]
+        store: PathBuf,
+        /// Secret that SurveyHero signs the callbacks with. Defaults to `SH_WEBHOOK_SECRET`.
+        #[clap(long)]
+        secret: Option<String>,
+    },
+}
+
+#[derive(clap::Parser, Clone)]
+pub enum TranslationLinksCmd {
+    /// Lists the translation links of a survey.
+    List {
+        /// ID of the survey.
+        #[clap(long)]
+        survey_id: usize,
+    },
+    /// Creates a translation link for every given language that does not have an active one.
+    Create {
+        /// ID of the survey.
+        #[clap(long)]
+        survey_id: usize,
+        /// Language code, e.g. `de`. Can be given several times.
+        #[clap(long = "lang", required = true)]
+        languages: Vec<String>,
+    },
+    /// Removes access for the translation links of the given languages.
+    Revoke {
+        /// ID of the survey.
+        #[clap(long)]
+        survey_id: usize,
+        /// Language code, e.g. `de`. Can be given several times.
+        #[clap(long = "lang", required_unless_present = "all")]
+        languages: Vec<String>,
+        /// Revoke the links of all languages.
+        #[clap(long, conflicts_with = "languages")]
+        all: bool,
+    },
+    /// Fails if a translation link is still active while the survey is open. Run it before
+    /// opening the survey, or regularly in CI.
+    Check {
+        /// ID of the survey.
+        #[clap(long)]
+        survey_id: usize,
+    },
+}
+
+#[derive(clap::Parser, Clone)]
+pub enum SnapshotCmd {
+    /// Lists the snapshots of a survey, from the oldest to the newest.
+    List {
+        /// ID of the survey.
+        #[clap(long)]
+        survey_id: usize,
+        /// Directory of the snapshot store.
+        #[clap(long, default_value = "snapshots")]
+        snapshots: PathBuf,
+    },
+    /// Shows what changed on SurveyHero between two snapshots, by default the two newest ones.
+    Diff {
+        /// ID of the survey.
+        #[clap(long)]
+        survey_id: usize,
+        /// Directory of the snapshot store.
+        #[clap(long, default_value = "snapshots")]
+        snapshots: PathBuf,
+        /// The older snapshot file.
+        #[clap(requires = "to")]
+        from: Option<PathBuf>,
+        /// The newer snapshot file.
+        to: Option<PathBuf>,
+    },
+}
+
+/// Parses a duration given as a number with an optional unit: `s` (the default), `m`, `h` or
+/// `d`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "s"),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{value}', expected e.g. 30m"))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("unknown unit '{unit}', expected s, m, h or d")),
+    };
+    number
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("duration '{value}' is too long"))
+}
+
+/// Resolves a survey path given on the command line. Absolute paths are used as they are.
+pub fn survey_path(path: &str) -> PathBuf {
+    if Path::new(path).is_absolute() {
+        return PathBuf::from(path);
+    }
+    PathBuf::from(format!("../surveys/{path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+        assert!(parse_duration("2w").is_err());
+        assert!(parse_duration("m").is_err());
+        assert_eq!(
+            parse_duration("999999999999999999d"),
+            Err("duration '999999999999999999d' is too long".to_owned())
+        );
     }
 }
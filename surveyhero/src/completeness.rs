@@ -0,0 +1,143 @@
+use crate::api::{Question, TextKind};
+use crate::normalize::Normalizer;
+use crate::SurveyData;
+use serde::Serialize;
+
+/// How much of a secondary language has been translated on SurveyHero.
+#[derive(Debug, Serialize)]
+pub struct LanguageCompleteness {
+    pub language: String,
+    /// Number of texts that need a translation.
+    pub total: usize,
+    pub translated: usize,
+    pub untranslated: Vec<UntranslatedText>,
+}
+
+impl LanguageCompleteness {
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        self.translated as f64 * 100.0 / self.total as f64
+    }
+}
+
+/// A text of a secondary language that is empty or still identical to English.
+#[derive(Debug, Serialize)]
+pub struct UntranslatedText {
+    pub question_id: u64,
+    /// The English question text.
+    pub question: String,
+    pub kind: TextKind,
+    /// The English text.
+    pub text: String,
+    /// The text is missing completely, instead of being a copy of the English one.
+    pub empty: bool,
+}
+
+/// Compares every secondary language of `data` with the main (English) one.
+///
+/// A text counts as untranslated if it is empty or identical to English. Texts without any
+/// letters (e.g. version numbers) do not need a translation and are not counted.
+pub fn translation_completeness(data: &SurveyData) -> Vec<LanguageCompleteness> {
+    data.secondary_languages
+        .iter()
+        .map(|(language, questions)| language_completeness(language, &data.main, questions))
+        .collect()
+}
+
+fn language_completeness(
+    language: &str,
+    english: &[Question],
+    translated: &[Question],
+) -> LanguageCompleteness {
+    let normalizer = Normalizer::markdown();
+    let mut completeness = LanguageCompleteness {
+        language: language.to_owned(),
+        total: 0,
+        translated: 0,
+        untranslated: vec![],
+    };
+
+    for original in english {
+        let translation = translated.iter().find(|q| q.id() == original.id());
+        let translated_texts = translation.map(|q| q.texts()).unwrap_or_default();
+        for (index, (kind, text)) in original.texts().into_iter().enumerate() {
+            if !text.chars().any(char::is_alphabetic) {
+                continue;
+            }
+            completeness.total += 1;
+            let translated_text = translated_texts
+                .get(index)
+                .filter(|(k, _)| *k == kind)
+                .map(|(_, t)| t.trim())
+                .unwrap_or_default();
+            if !translated_text.is_empty() && translated_text != text.trim() {
+                completeness.translated += 1;
+                continue;
+            }
+            completeness.untranslated.push(UntranslatedText {
+                question_id: original.id(),
+                question: original.text(),
+                kind,
+                text: normalizer.normalize(text),
+                empty: translated_text.is_empty(),
+            });
+        }
+    }
+    completeness
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(text: &str, choices: &[&str]) -> Question {
+        let choices = choices
+            .iter()
+            .enumerate()
+            .map(|(i, label)| serde_json::json!({ "choice_id": i, "label": label }))
+            .collect::<Vec<_>>();
+        serde_json::from_value(serde_json::json!({
+            "type": "choice_list",
+            "question_id": 1,
+            "question_text": text,
+            "description_text": "",
+            "choice_list": {
+                "choices": choices,
+                "settings": { "allows_multiple_choices": false },
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn counts_empty_and_copied_texts() {
+        let english = [question("Which version?", &["Yes", "No", "1.80", ""])];
+        let german = [question("Welche Version?", &["Ja", "No", "1.80", ""])];
+
+        let completeness = language_completeness("de", &english, &german);
+        assert_eq!(completeness.total, 3);
+        assert_eq!(completeness.translated, 2);
+        assert_eq!(completeness.untranslated.len(), 1);
+        assert_eq!(completeness.untranslated[0].text, "No");
+        assert!(!completeness.untranslated[0].empty);
+        assert!((completeness.percentage() - 200.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn counts_missing_questions_as_empty() {
+        let english = [question("Which version?", &["Yes"])];
+
+        let completeness = language_completeness("de", &english, &[]);
+        assert_eq!(completeness.total, 2);
+        assert_eq!(completeness.translated, 0);
+        assert!(completeness.untranslated.iter().all(|t| t.empty));
+    }
+
+    #[test]
+    fn nothing_to_translate_is_complete() {
+        let completeness = language_completeness("de", &[], &[]);
+        assert_eq!(completeness.percentage(), 100.0);
+    }
+}
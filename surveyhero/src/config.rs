@@ -0,0 +1,60 @@
+use crate::error::{Error, Result};
+use crate::normalize::Normalizer;
+use serde::Deserialize;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Per-survey settings of the verifier.
+///
+/// For a survey directory they are read from `verifier.toml` inside it, for a single Markdown
+/// file from `<name>.verifier.toml` next to it. All settings are optional:
+///
+/// ```toml
+/// [normalize]
+/// steps = ["inline-formatting", "decode-entities", "collapse-whitespace", "strip-open-response"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SurveyConfig {
+    /// Normalization applied to both sides before comparing texts.
+    #[serde(default)]
+    pub normalize: Normalizer,
+}
+
+impl SurveyConfig {
+    pub fn load(survey_path: &Path) -> Result<Self> {
+        let path = survey_file(survey_path, "verifier.toml");
+        let Some(text) = read_optional(&path)? else {
+            return Ok(Self::default());
+        };
+        log::debug!("Loading survey configuration from {}", path.display());
+        toml::from_str(&text).map_err(|error| Error::Config {
+            path,
+            message: error.to_string(),
+        })
+    }
+}
+
+/// Reads the file at `path`, `None` if it does not exist.
+pub(crate) fn read_optional(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(Error::io(path)(error)),
+    }
+}
+
+/// Returns the location of an auxiliary file belonging to the survey at `survey_path`.
+///
+/// Directories hold it directly (`<dir>/<name>`), single-file surveys keep it next to the
+/// Markdown file with the file stem as prefix (`<stem>.<name>`).
+pub fn survey_file(survey_path: &Path, name: &str) -> PathBuf {
+    if survey_path.is_dir() {
+        return survey_path.join(name);
+    }
+    let stem = survey_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    survey_path.with_file_name(format!("{stem}.{name}"))
+}
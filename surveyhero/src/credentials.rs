@@ -0,0 +1,349 @@
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+
+const USER_VAR: &str = "SH_API_USER";
+const TOKEN_VAR: &str = "SH_API_TOKEN";
+const HELPER_VAR: &str = "SH_API_CREDENTIAL_HELPER";
+
+/// The username and token of a SurveyHero API key (not of a SurveyHero user).
+#[derive(Clone)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// A place to look for [`Credentials`].
+#[derive(Debug, Clone)]
+pub enum CredentialSource {
+    /// Given on the command line.
+    Explicit {
+        username: Option<String>,
+        password: Option<String>,
+    },
+    /// The `SH_API_USER` and `SH_API_TOKEN` environment variables.
+    Environment,
+    /// A `.env` file with `SH_API_USER=...` and `SH_API_TOKEN=...` lines, like `.env.sample`.
+    DotEnv(PathBuf),
+    /// A TOML file with `username` and `token` keys.
+    ConfigFile(Option<PathBuf>),
+    /// A shell command printing `SH_API_USER=...` and `SH_API_TOKEN=...` lines, e.g. to read
+    /// the token from a password manager.
+    Helper(Option<String>),
+}
+
+impl CredentialSource {
+    /// The credentials of this source, or why it has none.
+    fn load(&self) -> Result<Credentials, String> {
+        match self {
+            Self::Explicit { username, password } => match (username, password) {
+                (Some(username), Some(password)) => Ok(Credentials {
+                    username: username.clone(),
+                    password: password.clone(),
+                }),
+                (None, None) => Err("not given".to_owned()),
+                (Some(_), None) => Err("--api-token is missing".to_owned()),
+                (None, Some(_)) => Err("--api-user is missing".to_owned()),
+            },
+            Self::Environment => {
+                let var = |name: &str| std::env::var(name).ok();
+                from_vars(var(USER_VAR), var(TOKEN_VAR))
+            }
+            Self::DotEnv(path) => {
+                let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+                let vars = parse_vars(&text);
+                from_vars(vars.get(USER_VAR).cloned(), vars.get(TOKEN_VAR).cloned())
+            }
+            Self::ConfigFile(None) => Err("neither XDG_CONFIG_HOME nor HOME is set".to_owned()),
+            Self::ConfigFile(Some(path)) => {
+                #[derive(Deserialize)]
+                struct ConfigFile {
+                    username: String,
+                    token: String,
+                }
+                let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+                let config: ConfigFile = toml::from_str(&text).map_err(|e| e.to_string())?;
+                Ok(Credentials {
+                    username: config.username,
+                    password: config.token,
+                })
+            }
+            Self::Helper(None) => Err(format!("{HELPER_VAR} is not set")),
+            Self::Helper(Some(command)) => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .output()
+                    .map_err(|e| e.to_string())?;
+                if !output.status.success() {
+                    return Err(format!(
+                        "failed with {}: {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ));
+                }
+                let vars = parse_vars(&String::from_utf8_lossy(&output.stdout));
+                from_vars(vars.get(USER_VAR).cloned(), vars.get(TOKEN_VAR).cloned())
+            }
+        }
+    }
+}
+
+impl fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Explicit { .. } => write!(f, "--api-user/--api-token flags"),
+            Self::Environment => write!(f, "{USER_VAR}/{TOKEN_VAR} environment variables"),
+            Self::DotEnv(path) => write!(f, "{}", path.display()),
+            Self::ConfigFile(None) => write!(f, "config file"),
+            Self::ConfigFile(Some(path)) => write!(f, "{}", path.display()),
+            Self::Helper(None) => write!(f, "credential helper"),
+            Self::Helper(Some(command)) => write!(f, "credential helper `{command}`"),
+        }
+    }
+}
+
+fn from_vars(username: Option<String>, password: Option<String>) -> Result<Credentials, String> {
+    match (username, password) {
+        (Some(username), Some(password)) => Ok(Credentials { username, password }),
+        (None, _) => Err(format!("{USER_VAR} is not set")),
+        (_, None) => Err(format!("{TOKEN_VAR} is not set")),
+    }
+}
+
+/// Parses `KEY=value` lines, ignoring comments and an `export` prefix and removing quotes
+/// around the value.
+fn parse_vars(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.trim().to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+/// The sources of the API credentials, tried in order until one has them.
+#[derive(Debug, Clone)]
+pub struct CredentialChain {
+    sources: Vec<CredentialSource>,
+}
+
+impl Default for CredentialChain {
+    /// The environment variables, `.env` in the current directory,
+    /// `$XDG_CONFIG_HOME/surveyhero/credentials.toml` (`~/.config` by default) and the command in
+    /// `SH_API_CREDENTIAL_HELPER`.
+    fn default() -> Self {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+        Self {
+            sources: vec![
+                CredentialSource::Environment,
+                CredentialSource::DotEnv(PathBuf::from(".env")),
+                CredentialSource::ConfigFile(
+                    config_dir.map(|dir| dir.join("surveyhero").join("credentials.toml")),
+                ),
+                CredentialSource::Helper(std::env::var(HELPER_VAR).ok()),
+            ],
+        }
+    }
+}
+
+impl CredentialChain {
+    /// Tries `source` before all other sources.
+    pub fn with_first(mut self, source: CredentialSource) -> Self {
+        self.sources.insert(0, source);
+        self
+    }
+
+    /// Runs `command` as the credential helper instead of the one in
+    /// `SH_API_CREDENTIAL_HELPER`.
+    pub fn with_helper(mut self, command: String) -> Self {
+        for source in &mut self.sources {
+            if let CredentialSource::Helper(helper) = source {
+                *helper = Some(command.clone());
+            }
+        }
+        self
+    }
+
+    /// The credentials of the first source that has them, or an error listing all sources
+    /// and why none of them had credentials.
+    pub fn resolve(&self) -> Result<Credentials> {
+        let mut tried = vec![];
+        for source in &self.sources {
+            match source.load() {
+                Ok(credentials) => {
+                    log::debug!("Using SurveyHero credentials from {source}");
+                    return Ok(credentials);
+                }
+                Err(reason) => tried.push(format!("{source}: {reason}")),
+            }
+        }
+        Err(Error::MissingCredentials { tried })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn explicit(username: Option<&str>, password: Option<&str>) -> CredentialSource {
+        CredentialSource::Explicit {
+            username: username.map(str::to_owned),
+            password: password.map(str::to_owned),
+        }
+    }
+
+    fn helper(command: &str) -> CredentialSource {
+        CredentialSource::Helper(Some(command.to_owned()))
+    }
+
+    fn resolve(sources: Vec<CredentialSource>) -> Result<Credentials> {
+        CredentialChain { sources }.resolve()
+    }
+
+    fn tried(sources: Vec<CredentialSource>) -> Vec<String> {
+        match resolve(sources) {
+            Err(Error::MissingCredentials { tried }) => tried,
+            result => panic!("expected missing credentials, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn parses_vars() {
+        let vars = parse_vars(
+            "# SurveyHero\n\
+             SH_API_USER = \"user name\"\n\
+             export SH_API_TOKEN='token=1'\n\
+             \n\
+             #SH_API_URL=commented\n\
+             PLAIN=value # not a comment\n\
+             no separator\n",
+        );
+        assert_eq!(vars.len(), 3);
+        assert_eq!(vars[USER_VAR], "user name");
+        assert_eq!(vars[TOKEN_VAR], "token=1");
+        assert_eq!(vars["PLAIN"], "value # not a comment");
+    }
+
+    #[test]
+    fn tries_the_default_sources_in_order() {
+        let sources = CredentialChain::default().sources;
+        assert!(matches!(
+            &sources[..],
+            [
+                CredentialSource::Environment,
+                CredentialSource::DotEnv(dot_env),
+                CredentialSource::ConfigFile(_),
+                CredentialSource::Helper(_),
+            ] if dot_env == Path::new(".env")
+        ));
+    }
+
+    #[test]
+    fn uses_the_first_source_with_credentials() {
+        let credentials = resolve(vec![
+            explicit(None, None),
+            CredentialSource::DotEnv(PathBuf::from("/nonexistent/.env")),
+            helper("echo SH_API_USER=helper; echo SH_API_TOKEN=secret"),
+            explicit(Some("later"), Some("unused")),
+        ])
+        .unwrap();
+        assert_eq!(credentials.username, "helper");
+        assert_eq!(credentials.password, "secret");
+
+        // Explicit credentials added first win over all others
+        let chain = CredentialChain {
+            sources: vec![helper("echo SH_API_USER=helper; echo SH_API_TOKEN=secret")],
+        }
+        .with_first(explicit(Some("flag"), Some("token")));
+        assert_eq!(chain.resolve().unwrap().username, "flag");
+        // The helper command replaces the one from the environment
+        let chain = CredentialChain {
+            sources: vec![CredentialSource::Helper(None)],
+        }
+        .with_helper("echo SH_API_USER=other; echo SH_API_TOKEN=secret".to_owned());
+        assert_eq!(chain.resolve().unwrap().username, "other");
+    }
+
+    #[test]
+    fn explains_missing_credentials() {
+        assert_eq!(
+            tried(vec![
+                explicit(Some("user"), None),
+                explicit(None, Some("token")),
+                helper("echo SH_API_USER=user"),
+                helper("echo SH_API_TOKEN=token"),
+                CredentialSource::Helper(None),
+                CredentialSource::ConfigFile(None),
+            ]),
+            [
+                "--api-user/--api-token flags: --api-token is missing",
+                "--api-user/--api-token flags: --api-user is missing",
+                "credential helper `echo SH_API_USER=user`: SH_API_TOKEN is not set",
+                "credential helper `echo SH_API_TOKEN=token`: SH_API_USER is not set",
+                "credential helper: SH_API_CREDENTIAL_HELPER is not set",
+                "config file: neither XDG_CONFIG_HOME nor HOME is set",
+            ]
+        );
+        let error = resolve(vec![explicit(None, None)]).unwrap_err().to_string();
+        assert!(
+            error.contains("\n  - --api-user/--api-token flags: not given\n"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn reports_failing_helpers() {
+        let reasons = tried(vec![helper("echo locked >&2; exit 3")]);
+        assert_eq!(reasons.len(), 1);
+        assert!(
+            reasons[0].starts_with("credential helper `echo locked >&2; exit 3`: failed with "),
+            "{}",
+            reasons[0]
+        );
+        assert!(reasons[0].ends_with(": locked"), "{}", reasons[0]);
+
+        // Credentials are not read from the output of a failing helper
+        let reasons = tried(vec![helper(
+            "echo SH_API_USER=user; echo SH_API_TOKEN=token; exit 1",
+        )]);
+        assert!(reasons[0].contains("failed with"), "{}", reasons[0]);
+    }
+
+    #[test]
+    fn redacts_the_token() {
+        let credentials = resolve(vec![explicit(Some("user"), Some("secret-token"))]).unwrap();
+        let debug = format!("{:?}", credentials);
+        assert!(
+            debug.contains("user") && !debug.contains("secret-token"),
+            "{}",
+            debug
+        );
+    }
+}
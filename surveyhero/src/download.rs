@@ -0,0 +1,180 @@
+use crate::config::read_optional;
+use crate::error::{Error, Result};
+use diffy::{ConflictStyle, MergeOptions};
+use std::path::{Path, PathBuf};
+
+/// What [`download_file`] did with a local file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadOutcome {
+    /// The file did not exist yet.
+    Created,
+    /// The file already matched SurveyHero.
+    Unchanged,
+    /// The file had no local changes and was replaced with the SurveyHero version.
+    Updated,
+    /// Local changes and SurveyHero changes were merged without conflicts.
+    Merged,
+    /// Local changes and SurveyHero changes overlap; the file contains conflict markers.
+    Conflicts,
+    /// The file differs from SurveyHero but had no baseline, so it is unclear which side
+    /// changed. The file was left untouched and the SurveyHero version became the baseline, so
+    /// the next download merges the SurveyHero changes made from now on.
+    BaselineCreated,
+}
+
+/// Location of the last downloaded version of `path`, used as the base of three-way merges.
+pub fn baseline_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default();
+    path.with_file_name(".baseline").join(file_name)
+}
+
+/// Updates `path` with `theirs`, the rendered SurveyHero version.
+///
+/// Changes made to the local file since the last download are kept: the local file, the new
+/// SurveyHero version and the last downloaded version (the baseline) are merged, and overlapping
+/// edits are written with conflict markers. The new SurveyHero version becomes the baseline.
+pub fn download_file(path: &Path, theirs: String) -> Result<DownloadOutcome> {
+    let baseline_path = baseline_path(path);
+    let local = read_optional(path)?;
+    let baseline = read_optional(&baseline_path)?;
+
+    let (contents, outcome) = match (local, baseline) {
+        (None, _) => (theirs.clone(), DownloadOutcome::Created),
+        (Some(local), _) if local == theirs => (local, DownloadOutcome::Unchanged),
+        (Some(local), None) => {
+            log::warn!(
+                "No baseline for {} found, keeping the local version",
+                path.display()
+            );
+            (local, DownloadOutcome::BaselineCreated)
+        }
+        (Some(local), Some(baseline)) if local == baseline => {
+            (theirs.clone(), DownloadOutcome::Updated)
+        }
+        (Some(local), Some(baseline)) => match MergeOptions::new()
+            .set_conflict_style(ConflictStyle::Merge)
+            .merge(&baseline, &local, &theirs)
+        {
+            Ok(merged) => (merged, DownloadOutcome::Merged),
+            Err(conflicts) => (conflicts, DownloadOutcome::Conflicts),
+        },
+    };
+
+    if !matches!(
+        outcome,
+        DownloadOutcome::Unchanged | DownloadOutcome::BaselineCreated
+    ) {
+        write(path, &contents)?;
+    }
+    write(&baseline_path, &theirs)?;
+    Ok(outcome)
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(Error::io(parent))?;
+    }
+    std::fs::write(path, contents).map_err(Error::io(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    const BASELINE: &str = "### One\n\nType: free form\n\n### Two\n\nType: free form\n";
+
+    /// Downloads `theirs` over a file that was downloaded as [`BASELINE`] and edited to `local`.
+    fn download(local: &str, theirs: &str) -> (DownloadOutcome, String) {
+        let dir = TempDir::new("download");
+        let path = dir.join("de.md");
+        write(&baseline_path(&path), BASELINE).unwrap();
+        write(&path, local).unwrap();
+
+        let outcome = download_file(&path, theirs.to_owned()).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(baseline_path(&path)).unwrap(),
+            theirs
+        );
+        (outcome, contents)
+    }
+
+    #[test]
+    fn creates_missing_files() {
+        let dir = TempDir::new("download");
+        let path = dir.join("de.md");
+        assert_eq!(
+            download_file(&path, BASELINE.to_owned()).unwrap(),
+            DownloadOutcome::Created
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), BASELINE);
+        assert_eq!(
+            download_file(&path, BASELINE.to_owned()).unwrap(),
+            DownloadOutcome::Unchanged
+        );
+    }
+
+    #[test]
+    fn keeps_files_without_baseline() {
+        let dir = TempDir::new("download");
+        let path = dir.join("de.md");
+        let local = BASELINE.replace("Two", "Zwei");
+        write(&path, &local).unwrap();
+        assert_eq!(
+            download_file(&path, BASELINE.to_owned()).unwrap(),
+            DownloadOutcome::BaselineCreated
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), local);
+        assert_eq!(
+            std::fs::read_to_string(baseline_path(&path)).unwrap(),
+            BASELINE
+        );
+
+        // From now on, SurveyHero changes are merged with the local ones
+        let theirs = BASELINE.replace("One", "Eins");
+        assert_eq!(
+            download_file(&path, theirs).unwrap(),
+            DownloadOutcome::Merged
+        );
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "### Eins\n\nType: free form\n\n### Zwei\n\nType: free form\n"
+        );
+    }
+
+    #[test]
+    fn updates_files_without_local_changes() {
+        let theirs = BASELINE.replace("Two", "Zwei");
+        assert_eq!(
+            download(BASELINE, &theirs),
+            (DownloadOutcome::Updated, theirs)
+        );
+    }
+
+    #[test]
+    fn merges_local_and_surveyhero_changes() {
+        let local = BASELINE.replace("One", "Eins");
+        let theirs = BASELINE.replace("Two", "Zwei");
+        assert_eq!(
+            download(&local, &theirs),
+            (
+                DownloadOutcome::Merged,
+                "### Eins\n\nType: free form\n\n### Zwei\n\nType: free form\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn writes_conflict_markers() {
+        let local = BASELINE.replace("Two", "Zwei");
+        let theirs = BASELINE.replace("Two", "Deux");
+        let (outcome, contents) = download(&local, &theirs);
+        assert_eq!(outcome, DownloadOutcome::Conflicts);
+        assert_eq!(
+            contents,
+            "### One\n\nType: free form\n\n<<<<<<< ours\n### Zwei\n=======\n### Deux\n\
+             >>>>>>> theirs\n\nType: free form\n"
+        );
+    }
+}
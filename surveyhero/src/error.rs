@@ -0,0 +1,130 @@
+use reqwest::StatusCode;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Errors of the SurveyHero API client, the Markdown parser and the local files of a survey.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// SurveyHero rejected the API credentials (HTTP 401 or 403).
+    #[error("SurveyHero rejected the API credentials (HTTP {status}) while {action}: {body}")]
+    Unauthorized {
+        status: StatusCode,
+        action: String,
+        body: String,
+    },
+    /// The rate limit was still exceeded after all retries.
+    #[error(
+        "SurveyHero rate limit exceeded while {action}{}",
+        retry_after.map(|d| format!(", retry after {d:?}")).unwrap_or_default()
+    )]
+    RateLimited {
+        action: String,
+        retry_after: Option<Duration>,
+        body: String,
+    },
+    /// Any other unsuccessful HTTP status.
+    #[error("HTTP error status {status} while {action}:\n{body}")]
+    Http {
+        status: StatusCode,
+        action: String,
+        body: String,
+    },
+    /// The request could not be sent or the response could not be read.
+    #[error("request to SurveyHero failed: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error(
+        "no survey with ID {survey_id} in the account. Available surveys:\n{}",
+        available
+            .iter()
+            .map(|(id, title)| format!("id= {id} name= {title}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )]
+    UnknownSurvey {
+        survey_id: usize,
+        /// IDs and titles of the surveys of the account.
+        available: Vec<(usize, String)>,
+    },
+    /// A response body does not have the expected format.
+    #[error("cannot deserialize {what} from:\n{body}\nError: {source}")]
+    Deserialize {
+        what: String,
+        body: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    /// A list endpoint did not return its last page within the page limit.
+    #[error("SurveyHero returned more than {pages} pages of {what}")]
+    TooManyPages { what: String, pages: usize },
+    /// A syntax error in a Markdown question file, on the given (1-based) line.
+    #[error("line {line}: {message}")]
+    Markdown { line: usize, message: String },
+    #[error(
+        "no SurveyHero API credentials found. Tried:\n{}\nSee .env.sample for the expected \
+         variables.",
+        tried.iter().map(|t| format!("  - {t}")).collect::<Vec<_>>().join("\n")
+    )]
+    MissingCredentials {
+        /// Every source that was tried, with the reason it had no credentials.
+        tried: Vec<String>,
+    },
+    /// A cassette cannot be read or written, or does not contain a request.
+    #[error("cassette {}: {message}", path.display())]
+    Cassette { path: PathBuf, message: String },
+    /// A file or directory cannot be read or written. The path is missing for other I/O errors,
+    /// e.g. when binding a socket.
+    #[error(
+        "{}{source}",
+        path.as_ref().map(|p| format!("{}: ", p.display())).unwrap_or_default()
+    )]
+    Io {
+        path: Option<PathBuf>,
+        #[source]
+        source: std::io::Error,
+    },
+    /// A configuration file (e.g. `verifier.toml` or `suppressions.toml`) is invalid.
+    #[error("invalid {}: {message}", path.display())]
+    Config { path: PathBuf, message: String },
+    /// A stored snapshot or response cannot be parsed, on the given (1-based) line if known.
+    #[error(
+        "cannot parse {}{}: {source}",
+        path.display(),
+        line.map(|l| format!(":{l}")).unwrap_or_default()
+    )]
+    Parse {
+        path: PathBuf,
+        line: Option<usize>,
+        #[source]
+        source: serde_json::Error,
+    },
+    /// A push plan contains differences that have to be fixed on SurveyHero by hand first.
+    #[error(
+        "the question types or the order of the questions changed, fix them on SurveyHero first"
+    )]
+    PushBlocked,
+    /// Questions can only be created in the default language.
+    #[error("cannot create question '{question}' in a translation")]
+    CreateInTranslation { question: String },
+    #[error("the webhook secret must not be empty")]
+    EmptyWebhookSecret,
+}
+
+impl Error {
+    /// Returns a function that turns an I/O error about `path` into an [`Error::Io`], for
+    /// `map_err`.
+    pub(crate) fn io(path: &Path) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        move |source| Self::Io {
+            path: Some(path.to_owned()),
+            source,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
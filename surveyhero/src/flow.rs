@@ -0,0 +1,450 @@
+use crate::api::{self, ConditionAction};
+use crate::markdown::{self, Answers};
+use crate::report::{Finding, FindingKind, Report};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Where the survey continues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// The question with the given (0-based) position in the survey.
+    Question(usize),
+    End,
+}
+
+/// A step in the survey flow that does not simply continue with the next question.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jump {
+    /// Position of the question the jump starts from.
+    pub question: usize,
+    /// Position of the answer that triggers the jump, or `None` if the jump happens regardless of
+    /// the answer.
+    pub answer: Option<usize>,
+    pub target: Target,
+}
+
+/// The branching logic of a survey, with questions identified by their position.
+#[derive(Debug, Default)]
+pub struct FlowGraph {
+    pub jumps: Vec<Jump>,
+    /// Number of questions in the survey. Continuing after the last one ends the survey.
+    pub questions: usize,
+    /// Jumps of the Markdown file whose anchor does not match any heading.
+    pub broken_links: Vec<BrokenLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub question: usize,
+    pub answer: Option<usize>,
+    pub anchor: String,
+}
+
+/// A place where two flow graphs continue at different questions.
+#[derive(Debug)]
+pub struct FlowDiff {
+    pub question: usize,
+    pub answer: Option<usize>,
+    pub md: Target,
+    pub sh: Target,
+}
+
+impl FlowGraph {
+    /// Builds the flow graph from the `NEXT` links on answers and the `SURVEY FLOW` "Skip to"
+    /// directives of a questions file.
+    pub fn from_markdown(source: &str) -> Self {
+        static NEXT_REGEX: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"\[`NEXT`\]\(#(?<anchor>[^)]*)\)").unwrap());
+        static SKIP_REGEX: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"^>\s*Skip to `#+\s*(?<heading>[^`]*)`").unwrap());
+
+        // Anchors of headings, mapped to the position of the first question at or after them
+        let mut anchors = HashMap::new();
+        let mut pending_anchors = vec![];
+        let mut links = vec![];
+        let mut question: Option<usize> = None;
+        let mut answer = 0;
+        let mut in_answers = false;
+
+        for line in source.lines().map(|l| l.trim()) {
+            if let Some(heading) = line.strip_prefix("###") {
+                let index = question.map(|q| q + 1).unwrap_or(0);
+                question = Some(index);
+                answer = 0;
+                in_answers = false;
+                pending_anchors.push(anchor(heading));
+                for anchor in pending_anchors.drain(..) {
+                    anchors.entry(anchor).or_insert(index);
+                }
+            } else if let Some(heading) = line.strip_prefix("#") {
+                pending_anchors.push(anchor(heading.trim_start_matches('#')));
+            } else if line.starts_with("Type:") {
+                in_answers = true;
+            } else if let Some(captures) = SKIP_REGEX.captures(line) {
+                match question {
+                    Some(question) => links.push((question, None, anchor(&captures["heading"]))),
+                    None => log::warn!("Survey flow directive before the first question: {line}"),
+                }
+            } else if line.starts_with('-') && in_answers {
+                if let (Some(question), Some(captures)) = (question, NEXT_REGEX.captures(line)) {
+                    links.push((question, Some(answer), captures["anchor"].to_owned()));
+                }
+                answer += 1;
+            }
+        }
+
+        let mut graph = FlowGraph {
+            questions: question.map_or(0, |q| q + 1),
+            ..FlowGraph::default()
+        };
+        for (question, answer, anchor) in links {
+            let target = match anchors.get(&anchor) {
+                Some(target) => Target::Question(*target),
+                // A heading after the last question
+                None if pending_anchors.contains(&anchor) => Target::End,
+                None => {
+                    graph.broken_links.push(BrokenLink {
+                        question,
+                        answer,
+                        anchor,
+                    });
+                    continue;
+                }
+            };
+            graph.jumps.push(Jump {
+                question,
+                answer,
+                target,
+            });
+        }
+        graph
+    }
+
+    /// Builds the flow graph from the SurveyHero conditions of `questions`.
+    pub fn from_surveyhero(questions: &[api::Question], conditions: &[api::Condition]) -> Self {
+        let position = |id| questions.iter().position(|q| q.id() == id);
+
+        let mut graph = FlowGraph {
+            questions: questions.len(),
+            ..FlowGraph::default()
+        };
+        for condition in conditions {
+            let Some(question) = position(condition.question_id) else {
+                log::warn!(
+                    "Condition refers to unknown question {}",
+                    condition.question_id
+                );
+                continue;
+            };
+            let answer = match condition.choice_id {
+                Some(choice_id) => match questions[question].choice_index(choice_id) {
+                    Some(answer) => Some(answer),
+                    None => {
+                        log::warn!(
+                            "Condition of question {} refers to unknown choice {choice_id}",
+                            condition.question_id
+                        );
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let target = match condition.action {
+                ConditionAction::JumpToQuestion { question_id } => match position(question_id) {
+                    Some(target) => Target::Question(target),
+                    None => {
+                        log::warn!("Condition jumps to unknown question {question_id}");
+                        continue;
+                    }
+                },
+                ConditionAction::EndSurvey => Target::End,
+                ConditionAction::Unknown => {
+                    log::warn!(
+                        "Ignoring unsupported condition action of question {}",
+                        condition.question_id
+                    );
+                    continue;
+                }
+            };
+            graph.jumps.push(Jump {
+                question,
+                answer,
+                target,
+            });
+        }
+        graph
+    }
+
+    /// Where the survey continues after `question` has been answered with `answer`. Every
+    /// target after the last question is [`Target::End`].
+    pub fn next(&self, question: usize, answer: Option<usize>) -> Target {
+        let find = |answer| {
+            self.jumps
+                .iter()
+                .find(|j| j.question == question && j.answer == answer)
+                .map(|j| j.target)
+        };
+        match answer
+            .and_then(|a| find(Some(a)))
+            .or_else(|| find(None))
+            .unwrap_or(Target::Question(question + 1))
+        {
+            Target::Question(target) if target >= self.questions => Target::End,
+            target => target,
+        }
+    }
+
+    /// Compares two flow graphs at every question and answer that has a jump in either of them.
+    /// Branches with a broken link are skipped, as their target is unknown.
+    pub fn diff(&self, sh: &FlowGraph) -> Vec<FlowDiff> {
+        let branches = self
+            .jumps
+            .iter()
+            .chain(&sh.jumps)
+            .flat_map(|j| [(j.question, None), (j.question, j.answer)])
+            .collect::<BTreeSet<_>>();
+
+        branches
+            .into_iter()
+            .filter(|&(question, answer)| {
+                !self
+                    .broken_links
+                    .iter()
+                    .any(|l| l.question == question && l.answer == answer)
+            })
+            .filter_map(|(question, answer)| {
+                let (md, sh) = (self.next(question, answer), sh.next(question, answer));
+                (md != sh).then_some(FlowDiff {
+                    question,
+                    answer,
+                    md,
+                    sh,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Report {
+    /// Compares the survey flow described in `source` (the contents of `path`) with the skip
+    /// logic configured on SurveyHero.
+    pub fn check_flow(
+        &mut self,
+        path: &Path,
+        source: &str,
+        md_questions: &[markdown::Question],
+        sh_questions: &[api::Question],
+        conditions: &[api::Condition],
+    ) {
+        let md_flow = FlowGraph::from_markdown(source);
+        let sh_flow = FlowGraph::from_surveyhero(sh_questions, conditions);
+
+        let describe = |target, texts: &dyn Fn(usize) -> Option<String>| match target {
+            Target::Question(i) => texts(i)
+                .map(|text| format!("continues with '{text}'"))
+                .unwrap_or_else(|| "continues with end of survey".to_owned()),
+            Target::End => "continues with end of survey".to_owned(),
+        };
+        let md_target =
+            |target| describe(target, &|i| md_questions.get(i).map(|q| q.text.to_owned()));
+        let sh_target = |target| describe(target, &|i| sh_questions.get(i).map(|q| q.text()));
+
+        let differences = md_flow
+            .diff(&sh_flow)
+            .into_iter()
+            .map(|diff| {
+                (
+                    diff.question,
+                    diff.answer,
+                    md_target(diff.md),
+                    sh_target(diff.sh),
+                )
+            })
+            .chain(md_flow.broken_links.iter().map(|link| {
+                (
+                    link.question,
+                    link.answer,
+                    format!("links to unknown anchor '#{}'", link.anchor),
+                    sh_target(sh_flow.next(link.question, link.answer)),
+                )
+            }));
+
+        for (index, answer, md, sh) in differences {
+            let Some(question) = md_questions.get(index) else {
+                continue;
+            };
+            let answer = answer.and_then(|a| match &question.answers {
+                Answers::SelectOne(answers) | Answers::SelectMany(answers) => answers.get(a),
+                _ => None,
+            });
+
+            self.findings.push(Finding {
+                file: path.to_owned(),
+                line: markdown::line_number(source, answer.copied().unwrap_or(question.text)),
+                question_id: sh_questions.get(index).map(|q| q.id()),
+                question: match answer {
+                    Some(answer) => format!("{} -> {answer}", question.text),
+                    None => question.text.to_owned(),
+                },
+                kind: FindingKind::FlowDiffers,
+                md: Some(md),
+                sh: Some(sh),
+                english: None,
+                suppressed: false,
+                span: None,
+            });
+        }
+    }
+}
+
+/// The GitHub-style anchor of a Markdown heading.
+fn anchor(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SURVEY: &str = "\
+### Do you use Rust?
+
+Type: select one
+
+- Yes
+- No [`NEXT`](#thanks)
+
+## Usage
+
+### Why?
+
+Type: free form
+
+### How often?
+
+Type: select one
+
+- Daily
+- Never [`NEXT`](#why)
+
+> Skip to `## Thanks`
+
+## Thanks
+";
+
+    fn questions(count: u64) -> Vec<api::Question> {
+        (1..=count)
+            .map(|id| {
+                serde_json::from_value(json!({
+                    "type": "choice_list",
+                    "question_id": id,
+                    "question_text": format!("Question {id}"),
+                    "description_text": "",
+                    "choice_list": {
+                        "choices": [
+                            { "choice_id": id * 10 + 1, "label": "A" },
+                            { "choice_id": id * 10 + 2, "label": "B" },
+                        ],
+                        "settings": { "allows_multiple_choices": false },
+                    },
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn conditions(conditions: serde_json::Value) -> Vec<api::Condition> {
+        serde_json::from_value(conditions).unwrap()
+    }
+
+    #[test]
+    fn reads_next_links_and_skip_directives() {
+        let graph = FlowGraph::from_markdown(SURVEY);
+        assert_eq!(graph.questions, 3);
+        assert!(graph.broken_links.is_empty(), "{:?}", graph.broken_links);
+        assert_eq!(graph.next(0, Some(0)), Target::Question(1));
+        assert_eq!(graph.next(0, Some(1)), Target::End);
+        assert_eq!(graph.next(2, Some(1)), Target::Question(1));
+        assert_eq!(graph.next(2, Some(0)), Target::End);
+        assert_eq!(graph.next(1, None), Target::Question(2));
+    }
+
+    #[test]
+    fn reports_broken_links() {
+        let graph = FlowGraph::from_markdown(&SURVEY.replace("#why", "#unknown"));
+        assert_eq!(
+            graph.broken_links,
+            [BrokenLink {
+                question: 2,
+                answer: Some(1),
+                anchor: "unknown".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn reads_surveyhero_conditions() {
+        let graph = FlowGraph::from_surveyhero(
+            &questions(3),
+            &conditions(json!([
+                { "question_id": 1, "choice_id": 12, "action": { "type": "end_survey" } },
+                { "question_id": 3, "choice_id": 32, "action": { "type": "jump_to_question", "question_id": 2 } },
+                { "question_id": 3, "action": { "type": "end_survey" } },
+                { "question_id": 9, "action": { "type": "end_survey" } },
+            ])),
+        );
+        assert_eq!(graph.jumps.len(), 3);
+        assert!(graph.diff(&FlowGraph::from_markdown(SURVEY)).is_empty());
+    }
+
+    #[test]
+    fn continuing_after_the_last_question_ends_the_survey() {
+        let md = FlowGraph::from_markdown(
+            "### Q1\n\nType: select one\n\n- A\n- B\n\n### Q2\n\nType: select one\n\n- A\n- B\n",
+        );
+        let sh = FlowGraph::from_surveyhero(
+            &questions(2),
+            &conditions(json!([
+                { "question_id": 2, "choice_id": 22, "action": { "type": "end_survey" } },
+            ])),
+        );
+        assert_eq!(md.next(1, Some(1)), Target::End);
+        assert!(md.diff(&sh).is_empty());
+    }
+
+    #[test]
+    fn diffs_branches_of_both_graphs() {
+        let md = FlowGraph::from_markdown(SURVEY);
+        let sh = FlowGraph::from_surveyhero(
+            &questions(3),
+            &conditions(json!([
+                { "question_id": 1, "choice_id": 11, "action": { "type": "end_survey" } },
+            ])),
+        );
+        let diffs = md
+            .diff(&sh)
+            .into_iter()
+            .map(|d| (d.question, d.answer, d.md, d.sh))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diffs,
+            [
+                (0, Some(0), Target::Question(1), Target::End),
+                (0, Some(1), Target::End, Target::Question(1)),
+                (2, Some(1), Target::Question(1), Target::End),
+            ]
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use crate::api::{self, Element};
+use crate::markdown;
+use crate::normalize::Normalizer;
+use crate::report::{Finding, FindingKind, Report};
+use std::path::Path;
+
+/// What comes before a question (or after the last one) apart from the questions themselves.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Preamble {
+    /// Free text, with paragraphs separated by blank lines.
+    pub text: String,
+    /// A new section starts here: a `##` heading in Markdown, a page break on SurveyHero.
+    pub new_section: bool,
+}
+
+/// The texts and section boundaries of a survey, with one [`Preamble`] per question plus one
+/// for the end of the survey.
+#[derive(Debug, Default)]
+pub struct Layout {
+    pub preambles: Vec<Preamble>,
+}
+
+impl Layout {
+    /// Collects the paragraphs and `##` headings outside of questions. A question extends from
+    /// its `###` heading over its description and `Type:` line up to the last line of answers;
+    /// blockquotes are metadata and never part of the text.
+    pub fn from_markdown(source: &str) -> Self {
+        let mut layout = Layout::default();
+        let mut current = Preamble::default();
+        let mut in_question = false;
+        let mut in_answers = false;
+        let mut in_paragraph = false;
+
+        for line in source.lines().map(|l| l.trim()) {
+            if line.starts_with("###") {
+                layout.preambles.push(std::mem::take(&mut current));
+                in_question = true;
+                in_answers = false;
+            } else if let Some(heading) = line.strip_prefix('#') {
+                in_question = false;
+                current.new_section |= heading.starts_with('#');
+            } else if line.starts_with("Type:") {
+                in_answers = true;
+            } else if line.starts_with('>') || line.is_empty() {
+                in_paragraph &= !line.is_empty();
+                continue;
+            } else if in_question
+                && (!in_answers
+                    || line.starts_with('-')
+                    || line.ends_with(':')
+                    || line.starts_with("REPEAT"))
+            {
+                // Description or answers of the question
+            } else {
+                in_question = false;
+                if !current.text.is_empty() {
+                    current
+                        .text
+                        .push_str(if in_paragraph { "\n" } else { "\n\n" });
+                }
+                current.text.push_str(line);
+                in_paragraph = true;
+                continue;
+            }
+            in_paragraph = false;
+        }
+        layout.preambles.push(current);
+        layout
+    }
+
+    /// Collects the text elements and page breaks between the SurveyHero questions.
+    pub fn from_surveyhero(elements: &[Element]) -> Self {
+        let normalizer = Normalizer::markdown();
+        let mut layout = Layout::default();
+        let mut current = Preamble::default();
+
+        for element in elements {
+            match element {
+                Element::Text { text } => {
+                    let text = normalizer.normalize(text);
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    if !current.text.is_empty() {
+                        current.text.push_str("\n\n");
+                    }
+                    current.text.push_str(text.trim());
+                }
+                Element::PageBreak => current.new_section = true,
+                Element::Question { .. } => layout.preambles.push(std::mem::take(&mut current)),
+                Element::Unknown => {}
+            }
+        }
+        layout.preambles.push(current);
+        layout
+    }
+}
+
+impl Report {
+    /// Compares the texts between questions and the section boundaries of `source` (the
+    /// contents of `path`) with the text elements and page breaks on SurveyHero.
+    ///
+    /// Texts are compared with whitespace collapsed, as Markdown paragraphs are usually wrapped
+    /// differently than SurveyHero text blocks. Sections are not compared before the first
+    /// question (the first page of a survey has no page break) and after the last one.
+    pub fn check_layout(
+        &mut self,
+        path: &Path,
+        source: &str,
+        md_questions: &[markdown::Question],
+        elements: &[Element],
+        normalizer: &Normalizer,
+    ) {
+        let md_layout = Layout::from_markdown(source);
+        let sh_layout = Layout::from_surveyhero(elements);
+        let sh_questions = elements
+            .iter()
+            .filter_map(Element::question)
+            .collect::<Vec<&api::Question>>();
+
+        // Positions only line up if both sides have the same questions; otherwise only the
+        // preambles before the common questions are compared.
+        let mut count = md_questions.len().min(sh_questions.len());
+        if md_questions.len() == sh_questions.len() {
+            count += 1;
+        }
+        let compare_text = |text: &str| {
+            normalizer
+                .normalize(text)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        for (index, (md, sh)) in md_layout
+            .preambles
+            .iter()
+            .zip(&sh_layout.preambles)
+            .enumerate()
+            .take(count)
+        {
+            let question = md_questions.get(index);
+            let finding = |kind| Finding {
+                file: path.to_owned(),
+                line: question.and_then(|q| markdown::line_number(source, q.text)),
+                question_id: sh_questions.get(index).map(|q| q.id()),
+                question: match question {
+                    Some(question) => format!("before '{}'", question.text),
+                    None => "end of survey".to_owned(),
+                },
+                kind,
+                md: None,
+                sh: None,
+                english: None,
+                suppressed: false,
+                span: None,
+            };
+
+            if compare_text(&md.text) != compare_text(&sh.text) {
+                let describe = |text: &str| {
+                    if text.is_empty() {
+                        "no text".to_owned()
+                    } else {
+                        text.to_owned()
+                    }
+                };
+                self.findings.push(Finding {
+                    md: Some(describe(&md.text)),
+                    sh: Some(describe(&sh.text)),
+                    ..finding(FindingKind::TextDiffers)
+                });
+            }
+            if index > 0 && question.is_some() && md.new_section != sh.new_section {
+                let describe = |new_section| {
+                    if new_section {
+                        "starts a new section"
+                    } else {
+                        "continues the section"
+                    }
+                };
+                self.findings.push(Finding {
+                    md: Some(describe(md.new_section).to_owned()),
+                    sh: Some(describe(sh.new_section).to_owned()),
+                    ..finding(FindingKind::SectionDiffers)
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = include_str!("../tests/fixtures/mock/questions.md");
+    const ELEMENTS: &str = include_str!("../tests/fixtures/mock/42/elements.json");
+
+    fn preamble(text: &str, new_section: bool) -> Preamble {
+        Preamble {
+            text: text.to_owned(),
+            new_section,
+        }
+    }
+
+    fn check(source: &str, elements: &[Element]) -> Vec<(FindingKind, String)> {
+        let mut report = Report::default();
+        report.check_layout(
+            Path::new("questions.md"),
+            source,
+            &markdown::parse(source).unwrap(),
+            elements,
+            &Normalizer::default(),
+        );
+        report
+            .findings
+            .into_iter()
+            .map(|f| (f.kind, f.question))
+            .collect()
+    }
+
+    #[test]
+    fn reads_markdown_layout() {
+        let layout = Layout::from_markdown(SOURCE);
+        assert_eq!(
+            layout.preambles,
+            [
+                preamble("Welcome to the survey!", false),
+                preamble("", true),
+                preamble("", false),
+                preamble("", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_wrapped_lines_and_skips_answers() {
+        let layout = Layout::from_markdown(
+            "First line\nsecond line\n\nNext paragraph\n\n### Q\n\nDescription\n\nType: select one\n\n- A\n- B\n\n> Note\n\nAfter\n",
+        );
+        assert_eq!(
+            layout.preambles,
+            [
+                preamble("First line\nsecond line\n\nNext paragraph", false),
+                preamble("After", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_surveyhero_layout() {
+        let elements: Vec<Element> = serde_json::from_str(ELEMENTS).unwrap();
+        let layout = Layout::from_surveyhero(&elements);
+        assert_eq!(
+            layout.preambles,
+            [
+                preamble("Welcome to the survey!", false),
+                preamble("", true),
+                preamble("", false),
+                preamble("", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_text_and_section_differences() {
+        let elements: Vec<Element> = serde_json::from_str(ELEMENTS).unwrap();
+        assert_eq!(check(SOURCE, &elements), []);
+
+        let source = SOURCE
+            .replace("Welcome to the survey!", "Welcome!")
+            .replace("## Rust usage\n", "");
+        assert_eq!(
+            check(&source, &elements),
+            [
+                (
+                    FindingKind::TextDiffers,
+                    "before 'Do you use Rust?'".to_owned()
+                ),
+                (
+                    FindingKind::SectionDiffers,
+                    "before 'Why do you use Rust?'".to_owned()
+                ),
+            ]
+        );
+    }
+}
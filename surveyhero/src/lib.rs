@@ -1,23 +1,77 @@
 pub mod api;
+pub mod cassette;
 pub mod cli;
+pub mod completeness;
+pub mod config;
+pub mod credentials;
+pub mod download;
+pub mod error;
+pub mod flow;
+pub mod layout;
 pub mod markdown;
+pub mod normalize;
+pub mod patch;
+pub mod push;
+pub mod rate_limit;
 pub mod render;
+pub mod report;
+pub mod responses;
+pub mod retry;
+mod server;
+pub mod snapshot;
+pub mod suppress;
+#[cfg(test)]
+mod temp_dir;
+pub mod translations;
+pub mod webhook;
 
-use crate::{api::Question, cli::SharedArgs, markdown::Answers};
-impl markdown::Question<'_> {
-    pub fn compare(&self, other: &Question) -> Comparison {
-        if self.text != other.text() {
-            return Comparison::TitlesDiffer {
-                md: self.text.to_owned(),
-                sh: other.text().to_owned(),
-            };
+pub use error::{Error, Result};
+
+use crate::{
+    api::Question, credentials::CredentialChain, markdown::Answers, normalize::Normalizer,
+    retry::RetryPolicy,
+};
+use serde::{Deserialize, Serialize};
+
+impl<'a> markdown::Question<'a> {
+    /// Returns the first difference between this question and its SurveyHero counterpart.
+    pub fn compare(&self, other: &Question, normalizer: &Normalizer) -> Comparison<'a> {
+        self.compare_all(other, normalizer)
+            .into_iter()
+            .next()
+            .unwrap_or(Comparison::Equal)
+    }
+
+    /// Returns every difference between this question and its SurveyHero counterpart: a
+    /// differing question text and differences in the answers are reported together.
+    pub fn compare_all(&self, other: &Question, normalizer: &Normalizer) -> Vec<Comparison<'a>> {
+        let mut comparisons = vec![];
+        if normalizer.normalize(self.text) != normalizer.normalize(other.raw_text()) {
+            comparisons.push(Comparison::TitlesDiffer {
+                md: self.text,
+                sh: other.text(),
+            });
+        }
+        match self.compare_answers(other, normalizer) {
+            Comparison::Equal => {}
+            comparison => comparisons.push(comparison),
         }
+        comparisons
+    }
 
+    fn compare_answers(&self, other: &Question, normalizer: &Normalizer) -> Comparison<'a> {
         match (&self.answers, other) {
+            (Answers::Unsupported, Question::Unknown { .. }) => {}
+            (_, Question::Unknown { kind, .. }) => {
+                return Comparison::Unsupported {
+                    question: self.text,
+                    kind: kind.clone(),
+                };
+            }
             (markdown::Answers::FreeForm, _) => {
                 if !other.is_free_form() {
                     return Comparison::QuestionTypesDiffer {
-                        question: self.text.to_owned(),
+                        question: self.text,
                         md: QuestionType::FreeForm,
                         sh: other.into(),
                     };
@@ -26,15 +80,12 @@ impl markdown::Question<'_> {
             (markdown::Answers::SelectOne(answers), Question::ChoiceList { choice_list, .. })
                 if other.is_select_one() =>
             {
-                let mismatched = choice_list.mismatched_answers(answers);
+                let mismatched = choice_list.mismatched_answers(answers, normalizer);
                 if !mismatched.is_empty() {
                     return Comparison::AnswersDiffer(
                         mismatched
                             .into_iter()
-                            .map(|(s1, s2)| AnswerDiff {
-                                sh: s1,
-                                md: s2.to_string(),
-                            })
+                            .map(|(s1, s2)| AnswerDiff { sh: s1, md: s2 })
                             .collect(),
                     );
                 }
@@ -42,15 +93,12 @@ impl markdown::Question<'_> {
             (markdown::Answers::SelectMany(answers), Question::ChoiceList { choice_list, .. })
                 if other.is_select_many() =>
             {
-                let mismatched = choice_list.mismatched_answers(answers);
+                let mismatched = choice_list.mismatched_answers(answers, normalizer);
                 if !mismatched.is_empty() {
                     return Comparison::AnswersDiffer(
                         mismatched
                             .into_iter()
-                            .map(|(s1, s2)| AnswerDiff {
-                                sh: s1,
-                                md: s2.to_string(),
-                            })
+                            .map(|(s1, s2)| AnswerDiff { sh: s1, md: s2 })
                             .collect(),
                     );
                 }
@@ -61,63 +109,43 @@ impl markdown::Question<'_> {
                 },
                 Question::ChoiceTable { choice_table, .. },
             ) => {
-                let mismatched_rows = choice_table.mismatched_rows(answers1);
-                if !mismatched_rows.is_empty() {
-                    return Comparison::MatrixAnswersDiffer(
-                        mismatched_rows
-                            .into_iter()
-                            .map(|(s1, s2)| AnswerDiff {
-                                sh: s1,
-                                md: s2.to_string(),
-                            })
-                            .collect(),
-                    );
-                }
-                let mismatched_columns = choice_table.mismatched_columns(answers2);
-                if !mismatched_columns.is_empty() {
+                let mut mismatched = choice_table.mismatched_rows(answers1, normalizer);
+                mismatched.extend(choice_table.mismatched_columns(answers2, normalizer));
+                if !mismatched.is_empty() {
                     return Comparison::MatrixAnswersDiffer(
-                        mismatched_columns
+                        mismatched
                             .into_iter()
-                            .map(|(s1, s2)| AnswerDiff {
-                                sh: s1,
-                                md: s2.to_string(),
-                            })
+                            .map(|(s1, s2)| AnswerDiff { sh: s1, md: s2 })
                             .collect(),
                     );
                 }
             }
             (markdown::Answers::RatingScale, Question::RatingScale { .. }) => {}
             (markdown::Answers::Ranking(answers), Question::Ranking { ranking, .. }) => {
-                let mismatched = ranking.mismatched_answers(answers);
+                let mismatched = ranking.mismatched_answers(answers, normalizer);
                 if !mismatched.is_empty() {
                     return Comparison::AnswersDiffer(
                         mismatched
                             .into_iter()
-                            .map(|(s1, s2)| AnswerDiff {
-                                sh: s1,
-                                md: s2.to_string(),
-                            })
+                            .map(|(s1, s2)| AnswerDiff { sh: s1, md: s2 })
                             .collect(),
                     );
                 }
             }
             (Answers::InputList(answers), Question::InputList { input_list, .. }) => {
-                let mismatched = input_list.mismatched_answers(answers);
+                let mismatched = input_list.mismatched_answers(answers, normalizer);
                 if !mismatched.is_empty() {
                     return Comparison::AnswersDiffer(
                         mismatched
                             .into_iter()
-                            .map(|(s1, s2)| AnswerDiff {
-                                sh: s1,
-                                md: s2.to_string(),
-                            })
+                            .map(|(s1, s2)| AnswerDiff { sh: s1, md: s2 })
                             .collect(),
                     );
                 }
             }
             _ => {
                 return Comparison::QuestionTypesDiffer {
-                    question: self.text.to_owned(),
+                    question: self.text,
                     md: self.into(),
                     sh: other.into(),
                 };
@@ -128,31 +156,37 @@ impl markdown::Question<'_> {
     }
 }
 
-#[allow(dead_code)]
 #[derive(Debug)]
-pub struct AnswerDiff {
-    md: String,
-    sh: String,
+pub struct AnswerDiff<'a> {
+    pub md: &'a str,
+    pub sh: String,
 }
 
-#[allow(dead_code)]
 #[derive(Debug)]
-pub enum Comparison {
+pub enum Comparison<'a> {
     TitlesDiffer {
-        md: String,
+        md: &'a str,
         sh: String,
     },
     QuestionTypesDiffer {
-        question: String,
+        question: &'a str,
         md: QuestionType,
         sh: QuestionType,
     },
-    AnswersDiffer(Vec<AnswerDiff>),
-    MatrixAnswersDiffer(Vec<AnswerDiff>),
+    AnswersDiffer(Vec<AnswerDiff<'a>>),
+    MatrixAnswersDiffer(Vec<AnswerDiff<'a>>),
+    /// The SurveyHero question has a type the verifier does not support, so its answers
+    /// cannot be compared.
+    Unsupported {
+        question: &'a str,
+        /// The `type` sent by SurveyHero.
+        kind: String,
+    },
     Equal,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum QuestionType {
     FreeForm,
     SelectOne,
@@ -161,6 +195,24 @@ pub enum QuestionType {
     RatingScale,
     Ranking,
     InputList,
+    /// A question type the verifier does not support.
+    Unsupported,
+}
+
+impl std::fmt::Display for QuestionType {
+    /// Uses the same names as the `Type:` line of the Markdown files.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::FreeForm => "free form",
+            Self::SelectOne => "select one",
+            Self::SelectMany => "select all that apply",
+            Self::Matrix => "matrix",
+            Self::RatingScale => "rating scale",
+            Self::Ranking => "ranking",
+            Self::InputList => "input list",
+            Self::Unsupported => "unsupported",
+        })
+    }
 }
 
 impl From<&Question> for QuestionType {
@@ -179,6 +231,7 @@ impl From<&Question> for QuestionType {
             Question::RatingScale { .. } => QuestionType::RatingScale,
             Question::Ranking { .. } => QuestionType::Ranking,
             Question::InputList { .. } => QuestionType::InputList,
+            Question::Unknown { .. } => QuestionType::Unsupported,
             _ => QuestionType::Matrix,
         }
     }
@@ -194,74 +247,150 @@ impl From<&markdown::Question<'_>> for QuestionType {
             markdown::Answers::RatingScale => Self::RatingScale,
             markdown::Answers::Ranking(_) => Self::Ranking,
             markdown::Answers::InputList(_) => Self::InputList,
+            markdown::Answers::Unsupported => Self::Unsupported,
         }
     }
 }
 
-#[derive(Debug)]
-struct SHCreds {
-    username: String,
-    password: String,
+/// Creates an API client with the credentials from the default [`CredentialChain`].
+pub fn client_from_env() -> Result<api::Client> {
+    Ok(api::Client::from(async_client_from_env()?))
 }
 
-fn get_creds_from_env() -> SHCreds {
-    let username = match std::env::var("SH_API_USER") {
-        Ok(v) => v,
-        _ => {
-            panic!("Please ensure SH_API_USER env var is set.");
-        }
-    };
+/// Creates an asynchronous API client with the credentials from the default
+/// [`CredentialChain`].
+pub fn async_client_from_env() -> Result<api::AsyncClient> {
+    async_client(&CredentialChain::default())
+}
 
-    let password = match std::env::var("SH_API_TOKEN") {
-        Ok(v) => v,
-        _ => {
-            panic!("Please ensure SH_API_TOKEN env var is set.");
-        }
-    };
+/// Creates an asynchronous API client with the credentials from `credentials`.
+/// `SH_API_BASE_URL` replaces the SurveyHero API URL (e.g. with a mock server) and
+/// `SH_API_MAX_ATTEMPTS` the number of attempts per request.
+pub fn async_client(credentials: &CredentialChain) -> Result<api::AsyncClient> {
+    let creds = credentials.resolve()?;
+    let client = api::AsyncClient::new(creds.username, creds.password)
+        .with_retry_policy(RetryPolicy::from_env());
+    Ok(match std::env::var("SH_API_BASE_URL") {
+        Ok(base_url) => client.with_base_url(base_url),
+        Err(_) => client,
+    })
+}
 
-    SHCreds { username, password }
+/// Blocking version of [`fetch_survey_data`].
+pub fn fetch_surveyhero_data(client: &api::AsyncClient, survey_id: usize) -> Result<SurveyData> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(fetch_survey_data(client, survey_id))
 }
 
-pub fn fetch_surveyhero_data(args: &SharedArgs) -> anyhow::Result<SurveyData> {
-    let creds = get_creds_from_env();
-    let mut client = api::Client::new(creds.username, creds.password);
-    let surveys = client.fetch_surveys()?;
+/// Downloads everything `check` and `download` need. The secondary languages are fetched
+/// concurrently; the rate limiter of `client` keeps the requests within SurveyHero's limits.
+pub async fn fetch_survey_data(client: &api::AsyncClient, survey_id: usize) -> Result<SurveyData> {
+    let surveys = client.fetch_surveys().await?;
     let survey = surveys
         .iter()
-        .find(|s| s.survey_id == args.survey_id)
-        .ok_or_else(|| {
-            anyhow::anyhow!(
-                "no survey with ID {} in the account. Available surveys:\n{}",
-                args.survey_id,
-                surveys
-                    .iter()
-                    .map(|s| format!("id= {} name= {}", s.survey_id, &s.title))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            )
+        .find(|s| s.survey_id == survey_id)
+        .ok_or_else(|| Error::UnknownSurvey {
+            survey_id,
+            available: surveys
+                .iter()
+                .map(|s| (s.survey_id, s.title.clone()))
+                .collect(),
         })?;
-    let languages = client.fetch_secondary_languages(survey.survey_id)?;
+    let (languages, conditions, elements) = futures::try_join!(
+        client.fetch_secondary_languages(survey.survey_id),
+        client.fetch_conditions(survey.survey_id),
+        async {
+            log::debug!("Downloading English version");
+            client.fetch_elements(survey.survey_id, None).await
+        },
+    )?;
+    let main = elements
+        .iter()
+        .filter_map(|e| e.question())
+        .cloned()
+        .collect();
 
-    log::debug!("Downloading English version");
-    let main = client.fetch_questions(survey.survey_id, None)?;
-    let secondary_languages = languages
-        .into_iter()
-        .map(|l| {
+    let secondary_languages =
+        futures::future::try_join_all(languages.into_iter().map(|l| async move {
             log::debug!("Downloading {} version", l.code);
-            let questions = client.fetch_questions(survey.survey_id, Some(l.code.clone()))?;
-            let language = l.code.clone();
-            Ok::<_, anyhow::Error>((language, questions))
-        })
-        .collect::<Result<_, _>>()?;
+            let questions = client
+                .fetch_questions(survey.survey_id, Some(l.code.clone()))
+                .await?;
+            Ok::<_, Error>((l.code, questions))
+        }))
+        .await?;
 
     Ok(SurveyData {
         main,
         secondary_languages,
+        conditions,
+        elements,
     })
 }
 
-#[derive(Debug)]
+/// Blocking version of [`fetch_survey_infos`].
+pub fn fetch_surveyhero_infos(
+    client: &api::AsyncClient,
+    survey_id: Option<usize>,
+) -> Result<Vec<SurveyInfo>> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(fetch_survey_infos(client, survey_id))
+}
+
+/// Fetches the metadata and languages of the survey `survey_id`, or of every survey of the
+/// account. The metadata is taken from the survey list when SurveyHero includes it there; the
+/// remaining requests are sent concurrently.
+pub async fn fetch_survey_infos(
+    client: &api::AsyncClient,
+    survey_id: Option<usize>,
+) -> Result<Vec<SurveyInfo>> {
+    let surveys = match survey_id {
+        Some(survey_id) => vec![(survey_id, None)],
+        None => client
+            .fetch_surveys()
+            .await?
+            .into_iter()
+            .map(|s| (s.survey_id, s.details()))
+            .collect(),
+    };
+    futures::future::try_join_all(surveys.into_iter().map(|(survey_id, details)| async move {
+        let (details, mut languages) = futures::try_join!(
+            async {
+                match details {
+                    Some(details) => Ok(details),
+                    None => client.fetch_survey(survey_id).await,
+                }
+            },
+            client.fetch_languages(survey_id),
+        )?;
+        languages.sort_by_key(|l| !l.is_default);
+        Ok(SurveyInfo {
+            details,
+            languages: languages.into_iter().map(|l| l.code).collect(),
+        })
+    }))
+    .await
+}
+
+/// The metadata of a survey, as printed by the `surveys` command.
+#[derive(Debug, Serialize)]
+pub struct SurveyInfo {
+    #[serde(flatten)]
+    pub details: api::SurveyDetails,
+    /// Codes of all languages, the default one first.
+    pub languages: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SurveyData {
     pub main: Vec<Question>,
     pub secondary_languages: Vec<(String, Vec<Question>)>,
+    /// Skip logic of the survey, shared by all languages.
+    pub conditions: Vec<api::Condition>,
+    /// All elements of the main language in order, including texts and page breaks.
+    pub elements: Vec<api::Element>,
 }
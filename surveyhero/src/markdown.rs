@@ -1,32 +1,52 @@
-use anyhow::bail;
+use crate::error::{Error, Result};
+use std::ops::Range;
 use std::vec;
 
-pub fn parse(markdown: &str) -> anyhow::Result<Vec<Question<'_>>> {
+/// Returns a [`Error::Markdown`] for the given line.
+macro_rules! bail {
+    ($line:expr, $($arg:tt)*) => {
+        return Err(Error::Markdown {
+            line: $line,
+            message: format!($($arg)*),
+        })
+    };
+}
+
+pub fn parse(markdown: &str) -> Result<Vec<Question<'_>>> {
     let mut questions = Vec::new();
     let mut state = ParserState::None;
-    for line in markdown
+    let mut line_number = 0;
+    for (index, line) in markdown
         .lines()
         .map(|l| l.trim())
-        .filter(|l| !l.is_empty())
-        .filter(|l| !l.starts_with(">"))
+        .enumerate()
+        .filter(|(_, l)| !l.is_empty())
+        .filter(|(_, l)| !l.starts_with(">"))
     {
+        line_number = index + 1;
         if let Some(text) = line.strip_prefix("###") {
             let old_state = std::mem::replace(&mut state, ParserState::Text(text.trim()));
             match old_state {
                 ParserState::Question(q) if !q.is_empty() => questions.push(q),
                 ParserState::None => {}
-                ParserState::Text(q) => bail!("question without answers '{}'", q),
-                ParserState::Question(q) => bail!("question without answers '{}'", q.text),
+                ParserState::Text(q) => bail!(line_number, "question without answers '{}'", q),
+                ParserState::Question(q) => {
+                    bail!(line_number, "question without answers '{}'", q.text)
+                }
                 ParserState::HalfMatrixText(q) => {
-                    bail!("question without answers '{}'", q)
+                    bail!(line_number, "question without answers '{}'", q)
                 }
                 ParserState::HalfMatrix {
                     answers, text: q, ..
                 } if answers.is_empty() => {
-                    bail!("question without answers '{}'", q)
+                    bail!(line_number, "question without answers '{}'", q)
                 }
                 ParserState::HalfMatrix { text: q, .. } => {
-                    bail!("matrix question without second half of answers '{}'", q)
+                    bail!(
+                        line_number,
+                        "matrix question without second half of answers '{}'",
+                        q
+                    )
                 }
             }
         } else if let Some(stripped_text) = line.strip_prefix("Type: ") {
@@ -64,11 +84,22 @@ pub fn parse(markdown: &str) -> anyhow::Result<Vec<Question<'_>>> {
                         text,
                         answers: Answers::InputList(vec![]),
                     })
+                } else if typ.starts_with("unsupported") {
+                    ParserState::Question(Question {
+                        text,
+                        answers: Answers::Unsupported,
+                    })
                 } else {
-                    bail!("illegal question type: type='{}' question='{}'", typ, text);
+                    bail!(
+                        line_number,
+                        "illegal question type: type='{}' question='{}'",
+                        typ,
+                        text
+                    );
                 }
             } else {
                 bail!(
+                    line_number,
                     "illegal parser state: found type when state is '{:?}'",
                     state
                 );
@@ -103,17 +134,16 @@ pub fn parse(markdown: &str) -> anyhow::Result<Vec<Question<'_>>> {
                 ParserState::HalfMatrix { answers, .. } => {
                     answers.push(trim_answer(stripped_text.trim()));
                 }
-                _ => {
-                    //     bail!("illegal state. found answer when state is {:?}", state)
-                }
+                ParserState::None => bail!(line_number, "answer without a question '{}'", line),
+                _ => {}
             }
         } else if line.starts_with("REPEAT") {
-            let previous = questions.last().ok_or_else(|| {
+            let Some(previous) = questions.last() else {
                 match state.question_text() {
-                    Some(t) => anyhow::anyhow!("question repeats previous answer but there is no previous question '{}'", t),
-                    None => anyhow::anyhow!("question repeats previous answer but there is no previous question or text for the current question"),
+                    Some(t) => bail!(line_number, "question repeats previous answer but there is no previous question '{}'", t),
+                    None => bail!(line_number, "question repeats previous answer but there is no previous question or text for the current question"),
                 }
-            })?;
+            };
             state = match (state, &previous.answers) {
                 (
                     ParserState::HalfMatrixText(q),
@@ -150,6 +180,7 @@ pub fn parse(markdown: &str) -> anyhow::Result<Vec<Question<'_>>> {
                     answers: new.clone(),
                 }),
                 (state, _) => bail!(
+                    line_number,
                     "unexpected placement of the REPEAT keyword. State={:?}",
                     state
                 ),
@@ -174,7 +205,11 @@ pub fn parse(markdown: &str) -> anyhow::Result<Vec<Question<'_>>> {
                     },
                 }),
                 ParserState::HalfMatrix { text, .. } => {
-                    bail!("matrix question has no answers in first section '{}'", text)
+                    bail!(
+                        line_number,
+                        "matrix question has no answers in first section '{}'",
+                        text
+                    )
                 }
                 _ => state,
             };
@@ -185,7 +220,11 @@ pub fn parse(markdown: &str) -> anyhow::Result<Vec<Question<'_>>> {
     match state {
         ParserState::Question(q) if !q.is_empty() => questions.push(q),
         ParserState::None => {}
-        _ => bail!("end of input and not in a correct state {:?}", state),
+        _ => bail!(
+            line_number,
+            "end of input and not in a correct state {:?}",
+            state
+        ),
     }
     Ok(questions)
 }
@@ -206,6 +245,8 @@ impl<'a> Question<'a> {
 pub enum Answers<'a> {
     FreeForm,
     RatingScale,
+    /// A question type the verifier does not support, e.g. `Type: unsupported (slider)`.
+    Unsupported,
     Ranking(Vec<&'a str>),
     InputList(Vec<&'a str>),
     SelectOne(Vec<&'a str>),
@@ -229,6 +270,7 @@ impl Answers<'_> {
             } => answers1.is_empty() || answers2.is_empty(),
             Self::FreeForm => false,
             Self::RatingScale => false,
+            Self::Unsupported => false,
         }
     }
 }
@@ -263,6 +305,24 @@ impl<'a> ParserState<'a> {
     }
 }
 
+/// Returns the byte range of `fragment` inside `source`.
+///
+/// Every string slice produced by [`parse`] borrows from its input, so this can locate questions
+/// and answers without the parser keeping track of positions.
+pub fn span(source: &str, fragment: &str) -> Option<Range<usize>> {
+    let offset = (fragment.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+    if offset + fragment.len() > source.len() {
+        return None;
+    }
+    Some(offset..offset + fragment.len())
+}
+
+/// Returns the 1-based line number of `fragment` inside `source`, see [`span`].
+pub fn line_number(source: &str, fragment: &str) -> Option<usize> {
+    let span = span(source, fragment)?;
+    Some(source[..span.start].matches('\n').count() + 1)
+}
+
 fn trim_answer(answer: &str) -> &str {
     let next = answer.find("[`NEXT`]");
     let i = if let Some(i) = next {
@@ -272,3 +332,64 @@ fn trim_answer(answer: &str) -> &str {
     };
     answer[..i].trim()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_line(markdown: &str) -> usize {
+        match parse(markdown) {
+            Err(Error::Markdown { line, .. }) => line,
+            result => panic!("expected a markdown error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn parses_questions() {
+        let questions = parse(
+            "### Do you use Rust?\n\nType: select one\n\n- Yes\n- No [`NEXT`]\n\n\
+             ### How do you rate these tools?\n\nType: matrix\n\nRows:\n\n- Cargo\n\n\
+             Columns:\n\n- Good\n- Bad\n",
+        )
+        .unwrap();
+        assert_eq!(questions.len(), 2);
+        assert!(matches!(&questions[0].answers, Answers::SelectOne(a) if a == &["Yes", "No"]));
+        assert!(matches!(
+            &questions[1].answers,
+            Answers::Matrix { label1: "Rows:", answers1, answers2 }
+                if answers1 == &["Cargo"] && answers2 == &["Good", "Bad"]
+        ));
+    }
+
+    #[test]
+    fn reports_error_lines() {
+        // The missing type is noticed at the next question
+        assert_eq!(
+            error_line("### Why?\n\n### Do you use Rust?\n\nType: free form\n"),
+            3
+        );
+        assert_eq!(
+            error_line("### Why?\n\nType: free form\n\n### How?\n\nType: essay\n"),
+            7
+        );
+        assert_eq!(
+            error_line("Intro\n\n- Yes\n- No\n\n### Why?\n\nType: free form\n"),
+            3
+        );
+    }
+
+    #[test]
+    fn locates_fragments() {
+        let source = "### Do you use Rust?\n\nType: select one\n\n- Yes\n- No\n\n\
+                      ### Why?\n\nType: free form\n";
+        let questions = parse(source).unwrap();
+        let Answers::SelectOne(answers) = &questions[0].answers else {
+            panic!("unexpected answers {:?}", questions[0].answers);
+        };
+        let range = span(source, answers[1]).unwrap();
+        assert_eq!(range.start, source.find("No").unwrap());
+        assert_eq!(&source[range], "No");
+        assert_eq!(line_number(source, answers[1]), Some(6));
+        assert_eq!(line_number(source, questions[1].text), Some(8));
+    }
+}
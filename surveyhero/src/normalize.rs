@@ -0,0 +1,242 @@
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::sync::LazyLock;
+use unicode_normalization::UnicodeNormalization;
+
+/// A single text normalization step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    /// Convert inline HTML formatting (links, emphasis, code, line breaks) to Markdown.
+    InlineFormatting,
+    /// Decode all HTML character references, e.g. `&amp;`, `&#39;` or `&eacute;`.
+    DecodeEntities,
+    /// Apply Unicode canonical composition (NFC).
+    UnicodeNfc,
+    /// Replace typographic single and double quotes with their ASCII counterparts.
+    FoldQuotes,
+    /// Replace hyphen, dash and minus variants with `-`.
+    FoldDashes,
+    /// Replace non-breaking spaces with regular spaces and drop zero-width characters.
+    SpecialSpaces,
+    /// Collapse every run of whitespace (including newlines) into a single space and trim.
+    CollapseWhitespace,
+    /// Remove the `(open response)` marker from the end of an answer.
+    StripOpenResponse,
+}
+
+impl Step {
+    fn apply(self, text: String) -> String {
+        match self {
+            Self::InlineFormatting => inline_formatting(&text),
+            Self::DecodeEntities => html_escape::decode_html_entities(&text).into_owned(),
+            Self::UnicodeNfc => text.nfc().collect(),
+            Self::FoldQuotes => text
+                .chars()
+                .map(|c| match c {
+                    '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' | '\u{2032}' => '\'',
+                    '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{201f}' | '\u{2033}' => '"',
+                    c => c,
+                })
+                .collect(),
+            Self::FoldDashes => text
+                .chars()
+                .map(|c| match c {
+                    '\u{2010}'..='\u{2015}' | '\u{2212}' | '\u{fe63}' | '\u{ff0d}' => '-',
+                    c => c,
+                })
+                .collect(),
+            Self::SpecialSpaces => text
+                .chars()
+                .filter(|c| !matches!(c, '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{feff}'))
+                .map(|c| match c {
+                    '\u{a0}' | '\u{202f}' | '\u{2007}' => ' ',
+                    c => c,
+                })
+                .collect(),
+            Self::CollapseWhitespace => text.split_whitespace().collect::<Vec<_>>().join(" "),
+            Self::StripOpenResponse => match text.strip_suffix("(open response)") {
+                Some(t) => t.trim_end().to_owned(),
+                None => text,
+            },
+        }
+    }
+}
+
+/// A chain of normalization steps.
+///
+/// The chain used by `check` is applied to both the Markdown and the SurveyHero text before they
+/// are compared, so formatting differences between the two do not show up as drift.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Normalizer {
+    steps: Vec<Step>,
+}
+
+impl Normalizer {
+    pub fn new(steps: Vec<Step>) -> Self {
+        Self { steps }
+    }
+
+    /// Converts SurveyHero text into the Markdown we write into the survey files.
+    pub fn markdown() -> &'static Self {
+        static MARKDOWN: LazyLock<Normalizer> = LazyLock::new(|| {
+            Normalizer::new(vec![
+                Step::InlineFormatting,
+                Step::DecodeEntities,
+                Step::SpecialSpaces,
+            ])
+        });
+        &MARKDOWN
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        self.steps
+            .iter()
+            .fold(text.to_owned(), |text, step| step.apply(text))
+    }
+}
+
+impl Default for Normalizer {
+    /// The full chain, used for comparisons unless a survey configures its own.
+    fn default() -> Self {
+        Self::new(vec![
+            Step::InlineFormatting,
+            Step::DecodeEntities,
+            Step::UnicodeNfc,
+            Step::FoldQuotes,
+            Step::FoldDashes,
+            Step::SpecialSpaces,
+            Step::CollapseWhitespace,
+            Step::StripOpenResponse,
+        ])
+    }
+}
+
+fn inline_formatting(text: &str) -> String {
+    static LINK_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"<a href="(?<link>.*?)".*?>(?<text>.*?)</a>"#).unwrap());
+    static ITALICS_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"<em>(?<em>.*?)</em>|<i>(?<i>.*?)</i>").unwrap());
+    static BOLD_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"<strong>(?<strong>.*?)</strong>|<b>(?<b>.*?)</b>").unwrap());
+    static CODE_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"<code>(?<text>.*?)</code>").unwrap());
+    static BREAK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<br\s*/?>").unwrap());
+    static PARAGRAPH_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"</p>\s*<p[^>]*>").unwrap());
+    static PARAGRAPH_TAG_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"</?p(\s[^>]*)?>").unwrap());
+
+    // Note: we leave manually inserted newlines
+    // Unwanted newlines should be fixed on SurveyHero
+    let text = BREAK_REGEX.replace_all(text, "\n");
+
+    // Text blocks consist of <p> paragraphs, which become blank-line separated paragraphs
+    let text = PARAGRAPH_REGEX.replace_all(&text, "\n\n");
+    let text = PARAGRAPH_TAG_REGEX.replace_all(&text, "");
+
+    // Replace <a href="$link" ...>$text</a> with [$text]($link)
+    let text = LINK_REGEX.replace_all(&text, "[$text]($link)");
+
+    // Replace <em>$text</em> with *$text*,
+    // <strong>$text</strong> with **$text** and
+    // <code>$text</code> with `$text`
+    let text = wrap_tags(&ITALICS_REGEX, &text, "*");
+    let text = wrap_tags(&BOLD_REGEX, &text, "**");
+    CODE_REGEX.replace_all(&text, "`$text`").into_owned()
+}
+
+/// Replaces every match of `regex`, whose alternatives each capture the content of one tag, with
+/// the content surrounded by `marker`. The `regex` crate has no backreferences, so an opening
+/// tag only matches the same closing tag through one alternative per tag.
+fn wrap_tags<'t>(regex: &Regex, text: &'t str, marker: &str) -> Cow<'t, str> {
+    regex.replace_all(text, |captures: &Captures| {
+        let content = captures
+            .iter()
+            .skip(1)
+            .flatten()
+            .next()
+            .map_or("", |m| m.as_str());
+        format!("{marker}{content}{marker}")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_inline_formatting() {
+        let text = r#"<p>See <a href="https://example.com" target="_blank">the <em>docs</em></a></p><p>Use <code>cargo</code><br/>or <b>not</b></p>"#;
+        assert_eq!(
+            Step::InlineFormatting.apply(text.to_owned()),
+            "See [the *docs*](https://example.com)\n\nUse `cargo`\nor **not**"
+        );
+    }
+
+    #[test]
+    fn keeps_tags_starting_with_p() {
+        let text = "<p class=\"x\">A <pre>b</pre> <param></p>";
+        assert_eq!(
+            Step::InlineFormatting.apply(text.to_owned()),
+            "A <pre>b</pre> <param>"
+        );
+    }
+
+    #[test]
+    fn requires_matching_closing_tags() {
+        assert_eq!(
+            Step::InlineFormatting.apply("<i>a</em> <b>b</strong>".to_owned()),
+            "<i>a</em> <b>b</strong>"
+        );
+        assert_eq!(
+            Step::InlineFormatting.apply("<em>a</em> <strong>b</strong> <i>c</i>".to_owned()),
+            "*a* **b** *c*"
+        );
+    }
+
+    #[test]
+    fn folds_characters() {
+        assert_eq!(
+            Step::DecodeEntities.apply("Tom &amp; Jerry&#39;s".to_owned()),
+            "Tom & Jerry's"
+        );
+        assert_eq!(Step::UnicodeNfc.apply("e\u{301}".to_owned()), "\u{e9}");
+        assert_eq!(
+            Step::FoldQuotes.apply("\u{201c}it\u{2019}s\u{201d}".to_owned()),
+            "\"it's\""
+        );
+        assert_eq!(
+            Step::FoldDashes.apply("a\u{2013}b\u{2212}c".to_owned()),
+            "a-b-c"
+        );
+        assert_eq!(
+            Step::SpecialSpaces.apply("a\u{a0}b\u{200b}c".to_owned()),
+            "a bc"
+        );
+        assert_eq!(
+            Step::CollapseWhitespace.apply(" a \n\n b\t".to_owned()),
+            "a b"
+        );
+        assert_eq!(
+            Step::StripOpenResponse.apply("Other (open response)".to_owned()),
+            "Other"
+        );
+    }
+
+    #[test]
+    fn applies_steps_in_order() {
+        let text = "<p>It\u{2019}s&nbsp;a\n<em>test</em> (open response)</p>";
+        assert_eq!(Normalizer::default().normalize(text), "It's a *test*");
+        assert_eq!(
+            Normalizer::new(vec![Step::CollapseWhitespace]).normalize(text),
+            "<p>It\u{2019}s&nbsp;a <em>test</em> (open response)</p>"
+        );
+        assert_eq!(Normalizer::new(vec![]).normalize(text), text);
+    }
+}
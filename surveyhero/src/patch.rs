@@ -0,0 +1,152 @@
+use crate::report::{repo_path, Report};
+use std::path::Path;
+
+impl Report {
+    /// Returns a unified diff that brings `source`, the contents of `path`, in line with
+    /// SurveyHero, or `None` if there is nothing to change.
+    ///
+    /// Only differing question texts and answers are patched; suppressed differences are left
+    /// alone, and structural differences (missing questions, other question types or survey flow)
+    /// have to be fixed by hand.
+    pub fn patch(&self, path: &Path, source: &str) -> Option<String> {
+        let mut edits = self
+            .findings
+            .iter()
+            .filter(|f| f.file == path && !f.suppressed)
+            .filter_map(|f| Some((f.span.clone()?, f.sh.as_deref()?)))
+            .collect::<Vec<_>>();
+        edits.sort_by_key(|(span, _)| span.start);
+        edits.dedup_by_key(|(span, _)| span.start);
+
+        let mut patched = String::with_capacity(source.len());
+        let mut position = 0;
+        for (span, sh) in edits {
+            if span.start < position {
+                log::warn!("Skipping overlapping change in {}", path.display());
+                continue;
+            }
+            patched.push_str(&source[position..span.start]);
+            patched.push_str(&replacement(&source[span.clone()], sh));
+            position = span.end;
+        }
+        patched.push_str(&source[position..]);
+        if patched == source {
+            return None;
+        }
+
+        let path = repo_path(path);
+        Some(unified_diff(&path, source, &patched))
+    }
+}
+
+/// A unified diff from `old` to `new`, with `a/<name>` and `b/<name>` headers like `git diff`.
+pub(crate) fn unified_diff(name: &str, old: &str, new: &str) -> String {
+    let patch = diffy::DiffOptions::new()
+        .set_original_filename(format!("a/{name}"))
+        .set_modified_filename(format!("b/{name}"))
+        .create_patch(old, new);
+    // Empty context lines keep their leading space, as in the output of `git diff`
+    let formatter = diffy::PatchFormatter::new().suppress_blank_empty(false);
+    let diff = formatter.fmt_patch(&patch).to_string();
+    diff
+}
+
+/// The text that replaces the Markdown text `md` with the SurveyHero text `sh`, keeping the
+/// Markdown-only annotations and the line structure of the file intact.
+fn replacement(md: &str, sh: &str) -> String {
+    let sh = sh.split_whitespace().collect::<Vec<_>>().join(" ");
+    match md.strip_suffix("(open response)") {
+        Some(_) if !sh.ends_with("(open response)") => format!("{sh} (open response)"),
+        _ => sh,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markdown;
+    use crate::report::{Finding, FindingKind, Report};
+    use std::path::{Path, PathBuf};
+
+    const SOURCE: &str = "\
+### Do you use Rust?
+
+Type: select one
+
+- Yes
+- Other (open response)
+";
+
+    fn finding(md: &str, sh: &str, kind: FindingKind, suppressed: bool) -> Finding {
+        // Spans are computed from slices of the source, like those of the parsed questions
+        let start = SOURCE.find(md).unwrap();
+        let md = &SOURCE[start..start + md.len()];
+        Finding {
+            file: PathBuf::from("../surveys/2024/questions.md"),
+            line: markdown::line_number(SOURCE, md),
+            question_id: Some(1),
+            question: "Do you use Rust?".to_owned(),
+            kind,
+            md: Some(md.to_owned()),
+            sh: Some(sh.to_owned()),
+            english: None,
+            suppressed,
+            span: markdown::span(SOURCE, md),
+        }
+    }
+
+    fn patch(findings: Vec<Finding>) -> Option<String> {
+        let report = Report {
+            findings,
+            ..Report::default()
+        };
+        report.patch(Path::new("../surveys/2024/questions.md"), SOURCE)
+    }
+
+    #[test]
+    fn replaces_texts_with_surveyhero() {
+        let patch = patch(vec![
+            finding(
+                "Do you use Rust?",
+                "Do you use\nRust today?",
+                FindingKind::TitleDiffers,
+                false,
+            ),
+            finding(
+                "Other (open response)",
+                "Something else",
+                FindingKind::AnswerDiffers,
+                false,
+            ),
+        ])
+        .unwrap();
+        assert_eq!(
+            patch,
+            "\
+--- a/surveys/2024/questions.md
++++ b/surveys/2024/questions.md
+@@ -1,6 +1,6 @@
+-### Do you use Rust?
++### Do you use Rust today?
+ 
+ Type: select one
+ 
+ - Yes
+-- Other (open response)
++- Something else (open response)
+"
+        );
+    }
+
+    #[test]
+    fn skips_suppressed_and_structural_findings() {
+        let mut missing = finding("Yes", "Yes", FindingKind::MissingOnline, false);
+        missing.span = None;
+        assert_eq!(
+            patch(vec![
+                finding("Yes", "Sure", FindingKind::AnswerDiffers, true),
+                missing,
+            ]),
+            None
+        );
+    }
+}
@@ -0,0 +1,887 @@
+use crate::api::{
+    self, ChoiceListPayload, ChoiceTablePayload, InputListPayload, LabelPayload, QuestionPayload,
+    RankingPayload, SettingsPayload, TextKind,
+};
+use crate::error::{Error, Result};
+use crate::markdown::{self, Answers};
+use crate::normalize::Normalizer;
+use crate::QuestionType;
+use regex::Regex;
+use serde_json::Map;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// A change needed to bring SurveyHero in line with the Markdown questions.
+#[derive(Debug)]
+pub enum PushAction {
+    /// A question that does not exist on SurveyHero yet; it is appended to the survey.
+    Create { question: QuestionPayload },
+    /// A question whose texts or answers differ.
+    Update {
+        question_id: u64,
+        question: QuestionPayload,
+        /// Human readable list of what changes.
+        changes: Vec<String>,
+    },
+    /// A difference that cannot be pushed and has to be fixed on SurveyHero by hand. A
+    /// `blocking` difference (a changed question type or order) means the Markdown and the
+    /// SurveyHero questions no longer line up, so the plan cannot be applied until it is fixed.
+    Manual {
+        question: String,
+        reason: String,
+        blocking: bool,
+    },
+}
+
+/// The changes `verifier push` sends to SurveyHero. Questions are matched by their text;
+/// questions whose text changed are matched by their position between the matching ones.
+#[derive(Debug, Default)]
+pub struct PushPlan {
+    pub actions: Vec<PushAction>,
+    /// The secondary language the plan updates, or `None` for the main language.
+    pub language: Option<String>,
+}
+
+impl PushPlan {
+    pub fn new(
+        md_questions: &[markdown::Question],
+        sh_questions: &[api::Question],
+        normalizer: &Normalizer,
+    ) -> Self {
+        let matches = match_questions(md_questions, sh_questions, normalizer);
+        let mut plan = PushPlan::default();
+        let mut last_match = None;
+        for (index, md) in md_questions.iter().enumerate() {
+            let action = match matches[index] {
+                Some(sh_index) if last_match.is_some_and(|last| sh_index < last) => {
+                    PushAction::Manual {
+                        question: md.text.to_owned(),
+                        reason: "question moved, reorder the questions on SurveyHero".to_owned(),
+                        blocking: true,
+                    }
+                }
+                Some(sh_index) => {
+                    last_match = Some(sh_index);
+                    match update(md, &sh_questions[sh_index], normalizer, false) {
+                        Some(action) => action,
+                        None => continue,
+                    }
+                }
+                // Created questions are appended, so only questions after the last existing one
+                // can be created
+                None if matches[index..].iter().any(Option::is_some) => PushAction::Manual {
+                    question: md.text.to_owned(),
+                    reason: "new question between existing ones, add it on SurveyHero".to_owned(),
+                    blocking: true,
+                },
+                None => match payload(md, None, normalizer) {
+                    Some(question) => PushAction::Create { question },
+                    None => PushAction::Manual {
+                        question: md.text.to_owned(),
+                        reason: format!("{} questions cannot be created", QuestionType::from(md)),
+                        blocking: false,
+                    },
+                },
+            };
+            plan.actions.push(action);
+        }
+        for (index, sh) in sh_questions.iter().enumerate() {
+            if !matches.contains(&Some(index)) {
+                plan.actions.push(PushAction::Manual {
+                    question: sh.text(),
+                    reason: "question does not exist in Markdown, delete it on SurveyHero"
+                        .to_owned(),
+                    blocking: false,
+                });
+            }
+        }
+        plan
+    }
+
+    /// Plans the upload of a translation. `sh_questions` are the SurveyHero questions in
+    /// `language`; they share their question and choice IDs with the main language. As the texts
+    /// differ from English, the questions are matched by position, like `check-translations`
+    /// does. Only texts can be translated, so questions and answers that do not exist online are
+    /// left for the English version to add.
+    pub fn translation(
+        language: &str,
+        md_questions: &[markdown::Question],
+        sh_questions: &[api::Question],
+        normalizer: &Normalizer,
+    ) -> Self {
+        let mut plan = PushPlan {
+            actions: vec![],
+            language: Some(language.to_owned()),
+        };
+        for (index, md) in md_questions.iter().enumerate() {
+            let action = match sh_questions.get(index) {
+                None => PushAction::Manual {
+                    question: md.text.to_owned(),
+                    reason: "question does not exist on SurveyHero, push the English version first"
+                        .to_owned(),
+                    blocking: false,
+                },
+                Some(sh) => match update(md, sh, normalizer, true) {
+                    Some(action) => action,
+                    None => continue,
+                },
+            };
+            plan.actions.push(action);
+        }
+        for sh in sh_questions.iter().skip(md_questions.len()) {
+            plan.actions.push(PushAction::Manual {
+                question: sh.text(),
+                reason: "question is missing in the translation".to_owned(),
+                blocking: false,
+            });
+        }
+        plan
+    }
+
+    /// Whether there is anything to send.
+    pub fn is_empty(&self) -> bool {
+        !self
+            .actions
+            .iter()
+            .any(|a| matches!(a, PushAction::Create { .. } | PushAction::Update { .. }))
+    }
+
+    /// Whether the plan contains a difference that has to be fixed by hand before it can be
+    /// applied.
+    pub fn is_blocked(&self) -> bool {
+        self.actions
+            .iter()
+            .any(|a| matches!(a, PushAction::Manual { blocking: true, .. }))
+    }
+
+    /// Sends the updates and creations of the plan to SurveyHero. Manual actions are skipped;
+    /// nothing is sent if one of them is blocking.
+    pub fn apply(&self, client: &mut api::Client, survey_id: usize) -> Result<()> {
+        if self.is_blocked() {
+            return Err(Error::PushBlocked);
+        }
+        for action in &self.actions {
+            match action {
+                PushAction::Update {
+                    question_id,
+                    question,
+                    ..
+                } => {
+                    log::info!("Updating question {question_id}");
+                    client.update_question(
+                        survey_id,
+                        *question_id,
+                        question,
+                        self.language.clone(),
+                    )?;
+                }
+                PushAction::Create { question } => {
+                    if self.language.is_some() {
+                        return Err(Error::CreateInTranslation {
+                            question: question.question_text().to_owned(),
+                        });
+                    }
+                    let question_id = client.create_question(survey_id, question)?;
+                    log::info!("Created question {question_id}");
+                }
+                PushAction::Manual { .. } => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Pairs every Markdown question with the index of its SurveyHero question (see
+/// [`match_texts`]). Unpaired Markdown questions are new.
+fn match_questions(
+    md_questions: &[markdown::Question],
+    sh_questions: &[api::Question],
+    normalizer: &Normalizer,
+) -> Vec<Option<usize>> {
+    match_texts(
+        md_questions.iter().map(|q| q.text),
+        sh_questions.iter().map(|q| q.raw_text()),
+        normalizer,
+    )
+}
+
+/// Pairs every Markdown text with the index of a SurveyHero text: first the equal texts (after
+/// normalization), then, between two such pairs, the remaining texts by position if there are as
+/// many on both sides (i.e. they were edited).
+fn match_texts<'a>(
+    md_texts: impl Iterator<Item = &'a str>,
+    sh_texts: impl Iterator<Item = &'a str>,
+    normalizer: &Normalizer,
+) -> Vec<Option<usize>> {
+    let md_texts = md_texts
+        .map(|t| normalizer.normalize(t))
+        .collect::<Vec<_>>();
+    let sh_texts = sh_texts
+        .map(|t| normalizer.normalize(t))
+        .collect::<Vec<_>>();
+    let mut matches = vec![None; md_texts.len()];
+    let mut used = vec![false; sh_texts.len()];
+    for (md, matched) in md_texts.iter().zip(&mut matches) {
+        *matched = sh_texts
+            .iter()
+            .enumerate()
+            .position(|(index, sh)| !used[index] && sh == md);
+        if let Some(index) = *matched {
+            used[index] = true;
+        }
+    }
+
+    let mut start = 0;
+    while start < md_texts.len() {
+        if matches[start].is_some() {
+            start += 1;
+            continue;
+        }
+        let end = (start..md_texts.len())
+            .find(|&i| matches[i].is_some())
+            .unwrap_or(md_texts.len());
+        let unused = unused_between(&matches, start, end, &used);
+        if unused.len() == end - start {
+            for (matched, index) in matches[start..end].iter_mut().zip(unused) {
+                *matched = Some(index);
+                used[index] = true;
+            }
+        }
+        start = end;
+    }
+    matches
+}
+
+/// The unused SurveyHero indexes between the pairs around the unpaired Markdown texts
+/// `start..end`.
+fn unused_between(
+    matches: &[Option<usize>],
+    start: usize,
+    end: usize,
+    used: &[bool],
+) -> Vec<usize> {
+    let after = matches[..start]
+        .iter()
+        .flatten()
+        .max()
+        .map_or(0, |index| index + 1);
+    let before = matches.get(end).copied().flatten().unwrap_or(used.len());
+    (after..before.max(after))
+        .filter(|&index| !used[index])
+        .collect()
+}
+
+/// Whether some unpaired Markdown texts sit between the same pairs as unpaired SurveyHero texts,
+/// i.e. edited texts are mixed with added or removed ones and cannot be told apart.
+fn is_ambiguous(matches: &[Option<usize>], sh_len: usize) -> bool {
+    let mut used = vec![false; sh_len];
+    for index in matches.iter().flatten() {
+        used[*index] = true;
+    }
+    (0..matches.len())
+        .filter(|&index| matches[index].is_none())
+        .any(|index| {
+            let end = (index..matches.len())
+                .find(|&i| matches[i].is_some())
+                .unwrap_or(matches.len());
+            !unused_between(matches, index, end, &used).is_empty()
+        })
+}
+
+impl fmt::Display for PushPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let markdown =
+            |question: &QuestionPayload| Normalizer::markdown().normalize(question.question_text());
+        for action in &self.actions {
+            match action {
+                PushAction::Create { question } => {
+                    writeln!(f, "+ create '{}'", markdown(question))?;
+                }
+                PushAction::Update {
+                    question_id,
+                    question,
+                    changes,
+                } => {
+                    writeln!(f, "~ update [{question_id}] '{}'", markdown(question))?;
+                    for change in changes {
+                        writeln!(f, "    {change}")?;
+                    }
+                }
+                PushAction::Manual {
+                    question,
+                    reason,
+                    blocking,
+                } => {
+                    let blocking = if *blocking { " (blocks --apply)" } else { "" };
+                    writeln!(f, "! manual '{question}': {reason}{blocking}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl QuestionPayload {
+    pub fn question_text(&self) -> &str {
+        match self {
+            Self::ChoiceList { question_text, .. }
+            | Self::Input { question_text, .. }
+            | Self::ChoiceTable { question_text, .. }
+            | Self::Ranking { question_text, .. }
+            | Self::InputList { question_text, .. } => question_text,
+        }
+    }
+}
+
+/// Compares a Markdown question with its SurveyHero counterpart. A `translation` may only change
+/// texts: added or removed answers have to come from the English version.
+fn update(
+    md: &markdown::Question,
+    sh: &api::Question,
+    normalizer: &Normalizer,
+    translation: bool,
+) -> Option<PushAction> {
+    let (md_type, sh_type) = (QuestionType::from(md), QuestionType::from(sh));
+    if md_type != sh_type {
+        return Some(PushAction::Manual {
+            question: md.text.to_owned(),
+            reason: format!("question type changes from {sh_type} to {md_type}"),
+            blocking: true,
+        });
+    }
+
+    let mut changes = vec![];
+    let mut structure_differs = false;
+    if normalizer.normalize(md.text) != normalizer.normalize(sh.raw_text()) {
+        changes.push(format!("text: '{}' -> '{}'", sh.text(), md.text));
+    }
+    let sh_texts = sh.texts();
+    for (kind, md_labels) in labels(md) {
+        let sh_labels = sh_texts
+            .iter()
+            .filter(|(k, _)| *k == kind)
+            .map(|(_, label)| *label)
+            .collect::<Vec<_>>();
+        let name = match kind {
+            TextKind::Row => "row",
+            TextKind::Column => "column",
+            _ => "answer",
+        };
+        let matches = match_texts(
+            md_labels.iter().copied(),
+            sh_labels.iter().copied(),
+            normalizer,
+        );
+        // Existing IDs must stay with their labels, or stored responses and skip logic would
+        // point to other answers
+        if is_ambiguous(&matches, sh_labels.len()) {
+            return Some(PushAction::Manual {
+                question: md.text.to_owned(),
+                reason: format!(
+                    "{name}s were edited next to added or removed ones, change them on SurveyHero"
+                ),
+                blocking: true,
+            });
+        }
+        let markdown = |label| Normalizer::markdown().normalize(label);
+        for (index, (md_label, matched)) in md_labels.iter().zip(&matches).enumerate() {
+            match matched {
+                Some(sh_index) => {
+                    let sh_label = sh_labels[*sh_index];
+                    if normalizer.normalize(sh_label) != normalizer.normalize(md_label) {
+                        changes.push(format!(
+                            "{name} {}: '{}' -> '{md_label}'",
+                            index + 1,
+                            markdown(sh_label)
+                        ));
+                    }
+                }
+                None => {
+                    structure_differs = true;
+                    changes.push(format!("add {name} '{md_label}'"));
+                }
+            }
+        }
+        for (sh_index, sh_label) in sh_labels.iter().enumerate() {
+            if !matches.contains(&Some(sh_index)) {
+                structure_differs = true;
+                changes.push(format!("remove {name} '{}'", markdown(sh_label)));
+            }
+        }
+        let order = matches.iter().flatten().collect::<Vec<_>>();
+        if order.windows(2).any(|pair| pair[0] > pair[1]) {
+            structure_differs = true;
+            changes.push(format!("reorder the {name}s"));
+        }
+    }
+    if changes.is_empty() {
+        return None;
+    }
+    if matches!(
+        md_type,
+        QuestionType::RatingScale | QuestionType::Unsupported
+    ) || (translation && structure_differs)
+    {
+        return Some(PushAction::Manual {
+            question: md.text.to_owned(),
+            reason: changes.join(", "),
+            blocking: false,
+        });
+    }
+
+    Some(PushAction::Update {
+        question_id: sh.id(),
+        question: payload(md, Some(sh), normalizer)?,
+        changes,
+    })
+}
+
+/// The answers, rows and columns of a Markdown question.
+fn labels<'q>(question: &'q markdown::Question) -> Vec<(TextKind, &'q [&'q str])> {
+    match &question.answers {
+        Answers::SelectOne(answers)
+        | Answers::SelectMany(answers)
+        | Answers::Ranking(answers)
+        | Answers::InputList(answers) => vec![(TextKind::Answer, answers)],
+        Answers::Matrix {
+            answers1, answers2, ..
+        } => vec![(TextKind::Row, answers1), (TextKind::Column, answers2)],
+        Answers::FreeForm | Answers::RatingScale | Answers::Unsupported => vec![],
+    }
+}
+
+/// Builds the SurveyHero representation of a Markdown question. Answers, rows and columns keep
+/// the IDs and other fields of the ones of `existing` they are paired with (by label, or by
+/// position for edited labels), and the description and settings of `existing` are sent back
+/// unchanged. Rating scales have no Markdown representation of their settings and cannot be
+/// pushed.
+fn payload(
+    question: &markdown::Question,
+    existing: Option<&api::Question>,
+    normalizer: &Normalizer,
+) -> Option<QuestionPayload> {
+    let with_ids = |labels: &[&str], kind| {
+        let fields = existing.map(|q| q.label_fields(kind)).unwrap_or_default();
+        let sh_texts = existing.map(|q| q.texts()).unwrap_or_default();
+        let matches = match_texts(
+            labels.iter().copied(),
+            sh_texts.iter().filter(|(k, _)| *k == kind).map(|(_, t)| *t),
+            normalizer,
+        );
+        labels
+            .iter()
+            .zip(matches)
+            .map(|(label, matched)| {
+                let (id, raw) = matched.and_then(|i| fields.get(i)).copied().unzip();
+                let id = id.flatten();
+                let mut raw = raw.cloned().unwrap_or_default();
+                let open_response = label.trim_end().ends_with(OPEN_RESPONSE);
+                if open_response || raw.contains_key(OPEN_RESPONSE_FIELD) {
+                    raw.insert(OPEN_RESPONSE_FIELD.to_owned(), open_response.into());
+                }
+                LabelPayload {
+                    choice_id: id.filter(|_| kind != TextKind::Row),
+                    row_id: id.filter(|_| kind == TextKind::Row),
+                    label: to_html(label),
+                    raw,
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let question_text = to_html(question.text);
+    let description_text = existing.map(|q| q.raw_description_text().to_owned());
+    let raw = existing.map(|q| q.raw().clone()).unwrap_or_default();
+    // The fields of the list of answers and of its settings
+    let (list_raw, settings_raw) = match existing {
+        Some(api::Question::ChoiceList { choice_list, .. }) => {
+            (choice_list.raw.clone(), choice_list.settings.raw.clone())
+        }
+        Some(api::Question::ChoiceTable { choice_table, .. }) => {
+            (choice_table.raw.clone(), Map::new())
+        }
+        Some(api::Question::Ranking { ranking, .. }) => (ranking.raw.clone(), Map::new()),
+        Some(api::Question::InputList { input_list, .. }) => (input_list.raw.clone(), Map::new()),
+        _ => (Map::new(), Map::new()),
+    };
+
+    Some(match &question.answers {
+        Answers::FreeForm => QuestionPayload::Input {
+            question_text,
+            description_text,
+            raw,
+        },
+        Answers::SelectOne(answers) | Answers::SelectMany(answers) => QuestionPayload::ChoiceList {
+            question_text,
+            description_text,
+            choice_list: ChoiceListPayload {
+                choices: with_ids(answers, TextKind::Answer),
+                settings: SettingsPayload {
+                    allows_multiple_choices: matches!(question.answers, Answers::SelectMany(_)),
+                    raw: settings_raw,
+                },
+                raw: list_raw,
+            },
+            raw,
+        },
+        Answers::Matrix {
+            answers1, answers2, ..
+        } => QuestionPayload::ChoiceTable {
+            question_text,
+            description_text,
+            choice_table: ChoiceTablePayload {
+                rows: with_ids(answers1, TextKind::Row),
+                choices: with_ids(answers2, TextKind::Column),
+                raw: list_raw,
+            },
+            raw,
+        },
+        Answers::Ranking(answers) => QuestionPayload::Ranking {
+            question_text,
+            description_text,
+            ranking: RankingPayload {
+                choices: with_ids(answers, TextKind::Answer),
+                raw: list_raw,
+            },
+            raw,
+        },
+        Answers::InputList(answers) => QuestionPayload::InputList {
+            question_text,
+            description_text,
+            input_list: InputListPayload {
+                inputs: with_ids(answers, TextKind::Answer),
+                raw: list_raw,
+            },
+            raw,
+        },
+        Answers::RatingScale | Answers::Unsupported => return None,
+    })
+}
+
+/// The marker of answers with a free text field in the Markdown files.
+const OPEN_RESPONSE: &str = "(open response)";
+/// The field of a SurveyHero choice that adds a free text field to it.
+const OPEN_RESPONSE_FIELD: &str = "is_open_response";
+
+/// Converts the inline Markdown of the survey files to the HTML used by SurveyHero, the
+/// reverse of the `inline-formatting` normalization step. The "(open response)" marker is not
+/// part of the label on SurveyHero, [`payload`] sets the open response field instead.
+fn to_html(text: &str) -> String {
+    static LINK_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\[(?<text>[^\]]*)\]\((?<link>[^)]*)\)").unwrap());
+    static BOLD_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\*\*(?<text>.+?)\*\*").unwrap());
+    static ITALICS_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\*(?<text>.+?)\*").unwrap());
+    static CODE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`(?<text>[^`]*)`").unwrap());
+
+    let text = text.trim();
+    let text = text
+        .strip_suffix(OPEN_RESPONSE)
+        .map(str::trim_end)
+        .unwrap_or(text);
+    let text = html_escape::encode_text(text);
+    let text = LINK_REGEX.replace_all(&text, r#"<a href="$link">$text</a>"#);
+    let text = BOLD_REGEX.replace_all(&text, "<strong>$text</strong>");
+    let text = ITALICS_REGEX.replace_all(&text, "<em>$text</em>");
+    CODE_REGEX
+        .replace_all(&text, "<code>$text</code>")
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MARKDOWN: &str = "\
+### Do you use Rust?
+
+Type: select one
+
+- Yes
+- No
+
+### Why?
+
+Type: free form
+
+### Which editor?
+
+Type: select all that apply
+
+- VS Code
+- Other (open response)
+";
+
+    fn sh_questions() -> Vec<api::Question> {
+        serde_json::from_value(json!([
+            {
+                "type": "choice_list",
+                "question_id": 1,
+                "question_text": "Do you use Rust?",
+                "description_text": "Be honest",
+                "is_required": true,
+                "choice_list": {
+                    "choices": [
+                        { "choice_id": 11, "label": "Yes" },
+                        { "choice_id": 12, "label": "No", "position": 2 },
+                    ],
+                    "settings": { "allows_multiple_choices": false, "randomize_choices": true },
+                },
+            },
+            { "type": "input", "question_id": 2, "question_text": "Why?", "description_text": "" },
+            {
+                "type": "choice_list",
+                "question_id": 3,
+                "question_text": "Which editor?",
+                "description_text": "",
+                "choice_list": {
+                    "choices": [
+                        { "choice_id": 31, "label": "VS Code" },
+                        { "choice_id": 32, "label": "Other", "is_open_response": true },
+                    ],
+                    "settings": { "allows_multiple_choices": true },
+                },
+            },
+        ]))
+        .unwrap()
+    }
+
+    fn plan(markdown: &str) -> PushPlan {
+        PushPlan::new(
+            &markdown::parse(markdown).unwrap(),
+            &sh_questions(),
+            &Normalizer::default(),
+        )
+    }
+
+    #[test]
+    fn matching_questions_need_no_changes() {
+        let plan = plan(MARKDOWN);
+        assert!(plan.actions.is_empty(), "{}", plan);
+    }
+
+    #[test]
+    fn updates_changed_texts_with_existing_fields() {
+        let plan = plan(&MARKDOWN.replace("- No\n", "- Not yet\n"));
+        let [PushAction::Update {
+            question_id,
+            question,
+            changes,
+        }] = plan.actions.as_slice()
+        else {
+            panic!("{}", plan);
+        };
+        assert_eq!(*question_id, 1);
+        assert_eq!(changes, &["answer 2: 'No' -> 'Not yet'"]);
+        assert_eq!(
+            serde_json::to_value(question).unwrap(),
+            json!({
+                "type": "choice_list",
+                "question_text": "Do you use Rust?",
+                "description_text": "Be honest",
+                "is_required": true,
+                "choice_list": {
+                    "choices": [
+                        { "choice_id": 11, "label": "Yes" },
+                        { "choice_id": 12, "label": "Not yet", "position": 2 },
+                    ],
+                    "settings": { "allows_multiple_choices": false, "randomize_choices": true },
+                },
+            })
+        );
+    }
+
+    /// The choice IDs and labels a plan sends for the first question.
+    fn choices(plan: &PushPlan) -> Vec<(Option<u64>, String)> {
+        let [PushAction::Update {
+            question: QuestionPayload::ChoiceList { choice_list, .. },
+            ..
+        }] = plan.actions.as_slice()
+        else {
+            panic!("{}", plan);
+        };
+        choice_list
+            .choices
+            .iter()
+            .map(|c| (c.choice_id, c.label.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_choice_ids_with_their_labels() {
+        let inserted = plan(&MARKDOWN.replace("- Yes\n", "- Maybe\n- Yes\n"));
+        assert_eq!(
+            choices(&inserted),
+            [
+                (None, "Maybe".to_owned()),
+                (Some(11), "Yes".to_owned()),
+                (Some(12), "No".to_owned())
+            ]
+        );
+        assert!(!inserted.is_blocked());
+
+        let reordered = plan(&MARKDOWN.replace("- Yes\n- No\n", "- No\n- Yes\n"));
+        assert_eq!(
+            choices(&reordered),
+            [(Some(12), "No".to_owned()), (Some(11), "Yes".to_owned())]
+        );
+        assert!(reordered.to_string().contains("reorder the answers"));
+
+        let removed = plan(&MARKDOWN.replace("- Yes\n", ""));
+        assert_eq!(choices(&removed), [(Some(12), "No".to_owned())]);
+    }
+
+    #[test]
+    fn ambiguous_answer_changes_block_the_plan() {
+        // "Yes" was edited and an answer added next to it: which one keeps the ID is unclear
+        let plan = plan(&MARKDOWN.replace("- Yes\n", "- Sure\n- Sometimes\n"));
+        assert!(
+            matches!(
+                plan.actions.as_slice(),
+                [PushAction::Manual { blocking: true, .. }]
+            ),
+            "{}",
+            plan
+        );
+    }
+
+    #[test]
+    fn matches_edited_question_text_by_position() {
+        let plan = plan(&MARKDOWN.replace("### Why?", "### Why do you use Rust?"));
+        let [PushAction::Update {
+            question_id: 2,
+            changes,
+            ..
+        }] = plan.actions.as_slice()
+        else {
+            panic!("{}", plan);
+        };
+        assert_eq!(changes, &["text: 'Why?' -> 'Why do you use Rust?'"]);
+        assert!(!plan.is_blocked());
+    }
+
+    #[test]
+    fn inserted_question_blocks_the_plan() {
+        let plan = plan(&MARKDOWN.replace(
+            "### Why?",
+            "### How long have you used Rust?\n\nType: free form\n\n### Why?",
+        ));
+        let [PushAction::Manual {
+            question,
+            blocking: true,
+            ..
+        }] = plan.actions.as_slice()
+        else {
+            panic!("{}", plan);
+        };
+        assert_eq!(question, "How long have you used Rust?");
+        assert!(plan.is_blocked());
+        // Nothing is sent, so the client is never used
+        let mut client = api::Client::new(String::new(), String::new());
+        assert!(matches!(
+            plan.apply(&mut client, 1),
+            Err(Error::PushBlocked)
+        ));
+    }
+
+    #[test]
+    fn appends_questions_at_the_end() {
+        let plan = plan(&format!(
+            "{MARKDOWN}\n### Anything else?\n\nType: free form\n"
+        ));
+        let [PushAction::Create { question }] = plan.actions.as_slice() else {
+            panic!("{}", plan);
+        };
+        assert_eq!(
+            serde_json::to_value(question).unwrap(),
+            json!({ "type": "input", "question_text": "Anything else?" })
+        );
+        assert!(!plan.is_blocked());
+    }
+
+    #[test]
+    fn marks_open_response_answers() {
+        let open = plan(&format!(
+            "{MARKDOWN}\n### Which OS?\n\nType: select one\n\n- Linux\n- Other (open response)\n"
+        ));
+        let [PushAction::Create { question }] = open.actions.as_slice() else {
+            panic!("{}", open);
+        };
+        assert_eq!(
+            serde_json::to_value(question).unwrap()["choice_list"]["choices"],
+            json!([
+                { "label": "Linux" },
+                { "label": "Other", "is_open_response": true },
+            ])
+        );
+    }
+
+    #[test]
+    fn changed_order_and_type_block_the_plan() {
+        let (first, rest) = MARKDOWN.split_once("### Why?").unwrap();
+        let reordered = format!("### Why?{rest}\n{first}");
+        assert!(plan(&reordered).is_blocked());
+
+        let retyped = MARKDOWN.replace("Type: free form", "Type: select one\n\n- Fun");
+        let plan = plan(&retyped);
+        assert!(
+            matches!(
+                plan.actions.as_slice(),
+                [PushAction::Manual { blocking: true, .. }]
+            ),
+            "{}",
+            plan
+        );
+    }
+
+    #[test]
+    fn reports_questions_missing_in_markdown() {
+        let (without_editor, _) = MARKDOWN.split_once("### Which editor?").unwrap();
+        let plan = plan(without_editor);
+        assert!(
+            matches!(
+                plan.actions.as_slice(),
+                [PushAction::Manual { blocking: false, question, .. }] if question == "Which editor?"
+            ),
+            "{}",
+            plan
+        );
+    }
+
+    #[test]
+    fn translations_only_change_texts() {
+        let german = MARKDOWN
+            .replace("Do you use Rust?", "Benutzt du Rust?")
+            .replace("- VS Code\n", "- VS Code\n- Vim\n");
+        let plan = PushPlan::translation(
+            "de",
+            &markdown::parse(&german).unwrap(),
+            &sh_questions(),
+            &Normalizer::default(),
+        );
+        assert_eq!(plan.language.as_deref(), Some("de"));
+        assert!(
+            matches!(
+                plan.actions.as_slice(),
+                [
+                    PushAction::Update { question_id: 1, .. },
+                    PushAction::Manual {
+                        blocking: false,
+                        ..
+                    },
+                ]
+            ),
+            "{}",
+            plan
+        );
+    }
+
+    #[test]
+    fn converts_markdown_to_html() {
+        assert_eq!(
+            to_html("Use **[Rust](https://rust-lang.org)** & *`cargo`* (open response)"),
+            r#"Use <strong><a href="https://rust-lang.org">Rust</a></strong> &amp; <em><code>cargo</code></em>"#
+        );
+    }
+}
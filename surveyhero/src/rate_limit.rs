@@ -0,0 +1,119 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A token bucket shared by all requests of a client.
+///
+/// The bucket holds up to `capacity` tokens and gains `rate` tokens per second. Every request
+/// takes one token, waiting for the bucket to refill if it is empty, so short bursts are sent
+/// immediately while the long-term request rate never exceeds `rate`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    rate: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: u32, rate: f64) -> Self {
+        assert!(
+            capacity > 0 && rate > 0.0,
+            "the rate limiter must allow requests"
+        );
+        Self {
+            capacity: capacity.into(),
+            rate,
+            bucket: Mutex::new(Bucket {
+                tokens: capacity.into(),
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// The published limit of the SurveyHero API: 2 requests per second.
+    pub fn surveyhero() -> Self {
+        Self::new(2, 2.0)
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            log::trace!("Rate limited, waiting {wait:?}");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a token if one is available, and otherwise returns how long it takes until the next
+    /// one is.
+    fn try_acquire(&self) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_bursts_up_to_the_capacity() {
+        let limiter = RateLimiter::new(3, 2.0);
+        for _ in 0..3 {
+            assert_eq!(limiter.try_acquire(), None);
+        }
+        let wait = limiter.try_acquire().unwrap();
+        assert!(
+            wait > Duration::from_millis(400) && wait <= Duration::from_millis(500),
+            "{:?}",
+            wait
+        );
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let limiter = RateLimiter::new(1, 2.0);
+        assert_eq!(limiter.try_acquire(), None);
+        limiter.bucket.lock().unwrap().last_refill -= Duration::from_millis(500);
+        assert_eq!(limiter.try_acquire(), None);
+
+        // The bucket never holds more than its capacity
+        limiter.bucket.lock().unwrap().last_refill -= Duration::from_secs(10);
+        assert_eq!(limiter.try_acquire(), None);
+        assert!(limiter.try_acquire().is_some());
+    }
+
+    #[test]
+    fn acquire_waits_for_the_rate() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let limiter = RateLimiter::new(1, 20.0);
+        let start = Instant::now();
+        runtime.block_on(async {
+            for _ in 0..3 {
+                limiter.acquire().await;
+            }
+        });
+        assert!(
+            start.elapsed() >= Duration::from_millis(90),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+}
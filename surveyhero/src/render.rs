@@ -1,64 +1,116 @@
-use crate::api::Question;
+use crate::api::{Element, Question};
+use crate::normalize::Normalizer;
+use std::fmt;
+use std::fmt::Write;
 use std::io;
-use std::io::Write;
 use std::path::Path;
 
-#[allow(unused)]
 pub fn render_questions(questions: &[Question], file: &Path) -> io::Result<()> {
+    write_rendered(file, &render_markdown(questions))
+}
+
+/// Renders the whole survey, including texts and page breaks, see [`render_survey_markdown`].
+pub fn render_survey(elements: &[Element], file: &Path) -> io::Result<()> {
+    write_rendered(file, &render_survey_markdown(elements))
+}
+
+/// Renders the questions in the Markdown format of the survey files.
+pub fn render_markdown(questions: &[Question]) -> String {
+    let mut markdown = String::new();
+    for question in questions {
+        write_question(&mut markdown, question).expect("writing to a String cannot fail");
+    }
+    markdown
+}
+
+/// Renders all elements of a survey: texts become paragraphs between the questions and every
+/// page break starts a new `##` section.
+pub fn render_survey_markdown(elements: &[Element]) -> String {
+    let mut markdown = String::new();
+    write_elements(&mut markdown, elements).expect("writing to a String cannot fail");
+    markdown
+}
+
+fn write_rendered(file: &Path, markdown: &str) -> io::Result<()> {
     eprintln!("Rendering {}", file.display());
 
     std::fs::create_dir_all(file.parent().unwrap())?;
+    std::fs::write(file, markdown)
+}
 
-    let mut file = std::fs::File::create(file)?;
-    for question in questions {
-        writeln!(file, "### {}\n", question.text())?;
-        if !question.description_text().is_empty() {
-            writeln!(file, "{}\n", question.description_text())?;
+fn write_elements(file: &mut String, elements: &[Element]) -> fmt::Result {
+    let normalizer = Normalizer::markdown();
+    let mut page = 1;
+    for element in elements {
+        match element {
+            Element::Text { text } => {
+                let text = normalizer.normalize(text);
+                if !text.trim().is_empty() {
+                    writeln!(file, "{}\n", text.trim())?;
+                }
+            }
+            Element::PageBreak => {
+                page += 1;
+                writeln!(file, "## Page {page}\n")?;
+            }
+            Element::Question { question } => write_question(file, question)?,
+            Element::Unknown => {}
+        }
+    }
+    Ok(())
+}
+
+fn write_question(file: &mut String, question: &Question) -> fmt::Result {
+    writeln!(file, "### {}\n", question.text())?;
+    if !question.description_text().is_empty() {
+        writeln!(file, "{}\n", question.description_text())?;
+    }
+    match question {
+        Question::Input { .. } => {
+            writeln!(file, "Type: free form")?;
         }
-        match question {
-            Question::Input { .. } => {
-                writeln!(file, "Type: free form")?;
+        Question::ChoiceList { choice_list, .. } => {
+            if question.is_select_one() {
+                writeln!(file, "Type: select one")?;
+            } else {
+                writeln!(file, "Type: select all that apply")?;
             }
-            Question::ChoiceList { choice_list, .. } => {
-                if question.is_select_one() {
-                    writeln!(file, "Type: select one")?;
-                } else {
-                    writeln!(file, "Type: select all that apply")?;
-                }
-                writeln!(file)?;
+            writeln!(file)?;
 
-                for variant in choice_list.as_strs() {
-                    writeln!(file, "- {variant}")?;
-                }
+            for variant in choice_list.as_strs() {
+                writeln!(file, "- {variant}")?;
             }
-            Question::ChoiceTable { choice_table, .. } => {
-                writeln!(file, "Type: matrix\n")?;
-                writeln!(file, "Rows:\n")?;
-                for row in choice_table.rows_strs() {
-                    writeln!(file, "- {row}")?;
-                }
-                writeln!(file, "\nColumns:\n")?;
-                for col in choice_table.column_strs() {
-                    writeln!(file, "- {col}")?;
-                }
+        }
+        Question::ChoiceTable { choice_table, .. } => {
+            writeln!(file, "Type: matrix\n")?;
+            writeln!(file, "Rows:\n")?;
+            for row in choice_table.rows_strs() {
+                writeln!(file, "- {row}")?;
             }
-            Question::RatingScale { .. } => {
-                writeln!(file, "Type: rating scale\n")?;
+            writeln!(file, "\nColumns:\n")?;
+            for col in choice_table.column_strs() {
+                writeln!(file, "- {col}")?;
             }
-            Question::Ranking { ranking, .. } => {
-                writeln!(file, "Type: ranking\n")?;
-                for variant in ranking.as_strs() {
-                    writeln!(file, "- {variant}")?;
-                }
+        }
+        Question::RatingScale { .. } => {
+            writeln!(file, "Type: rating scale\n")?;
+        }
+        Question::Ranking { ranking, .. } => {
+            writeln!(file, "Type: ranking\n")?;
+            for variant in ranking.as_strs() {
+                writeln!(file, "- {variant}")?;
             }
-            Question::InputList { input_list, .. } => {
-                writeln!(file, "Type: input list\n")?;
-                for input in input_list.as_strs() {
-                    writeln!(file, "- {input}")?;
-                }
+        }
+        Question::InputList { input_list, .. } => {
+            writeln!(file, "Type: input list\n")?;
+            for input in input_list.as_strs() {
+                writeln!(file, "- {input}")?;
             }
         }
-        writeln!(file)?;
+        Question::Unknown { kind, .. } => {
+            writeln!(file, "Type: unsupported ({kind})")?;
+        }
     }
+    writeln!(file)?;
     Ok(())
 }
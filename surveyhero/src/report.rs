@@ -0,0 +1,643 @@
+use crate::suppress::{Suppression, Suppressions};
+use crate::{api, markdown, normalize::Normalizer, Comparison, QuestionType};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+/// Output format of a check report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Human readable output.
+    Text,
+    /// A JSON document with all checked files and findings.
+    Json,
+    /// JUnit XML, one test suite per checked file.
+    Junit,
+    /// SARIF 2.1.0, for code scanning tools.
+    Sarif,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingKind {
+    TitleDiffers,
+    QuestionTypeDiffers,
+    AnswerDiffers,
+    MatrixAnswerDiffers,
+    MissingOnline,
+    MissingInMarkdown,
+    AnswerCountDiffers,
+    MatrixShapeDiffers,
+    MissingInTranslation,
+    ExtraInTranslation,
+    FlowDiffers,
+    TextDiffers,
+    SectionDiffers,
+    UnsupportedQuestion,
+}
+
+impl FindingKind {
+    const ALL: [FindingKind; 14] = [
+        FindingKind::TitleDiffers,
+        FindingKind::QuestionTypeDiffers,
+        FindingKind::AnswerDiffers,
+        FindingKind::MatrixAnswerDiffers,
+        FindingKind::MissingOnline,
+        FindingKind::MissingInMarkdown,
+        FindingKind::AnswerCountDiffers,
+        FindingKind::MatrixShapeDiffers,
+        FindingKind::MissingInTranslation,
+        FindingKind::ExtraInTranslation,
+        FindingKind::FlowDiffers,
+        FindingKind::TextDiffers,
+        FindingKind::SectionDiffers,
+        FindingKind::UnsupportedQuestion,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TitleDiffers => "title-differs",
+            Self::QuestionTypeDiffers => "question-type-differs",
+            Self::AnswerDiffers => "answer-differs",
+            Self::MatrixAnswerDiffers => "matrix-answer-differs",
+            Self::MissingOnline => "missing-online",
+            Self::MissingInMarkdown => "missing-in-markdown",
+            Self::AnswerCountDiffers => "answer-count-differs",
+            Self::MatrixShapeDiffers => "matrix-shape-differs",
+            Self::MissingInTranslation => "missing-in-translation",
+            Self::ExtraInTranslation => "extra-in-translation",
+            Self::FlowDiffers => "flow-differs",
+            Self::TextDiffers => "text-differs",
+            Self::SectionDiffers => "section-differs",
+            Self::UnsupportedQuestion => "unsupported-question",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::TitleDiffers => "question text differs",
+            Self::QuestionTypeDiffers => "question type differs",
+            Self::AnswerDiffers => "answer differs",
+            Self::MatrixAnswerDiffers => "matrix row or column differs",
+            Self::MissingOnline => "question is missing in the online version",
+            Self::MissingInMarkdown => "question is missing in the markdown version",
+            Self::AnswerCountDiffers => "number of answers differs from English",
+            Self::MatrixShapeDiffers => "matrix rows or columns differ from English",
+            Self::MissingInTranslation => "question is missing in the translation",
+            Self::ExtraInTranslation => "question does not exist in English",
+            Self::FlowDiffers => "survey flow differs",
+            Self::TextDiffers => "text between questions differs",
+            Self::SectionDiffers => "section boundary differs from page breaks",
+            Self::UnsupportedQuestion => "question type is not supported by the verifier",
+        }
+    }
+}
+
+/// A single difference between a Markdown file and SurveyHero.
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub file: PathBuf,
+    /// 1-based line in `file`, if the finding can be located there.
+    pub line: Option<usize>,
+    /// SurveyHero ID of the question, if the question exists online.
+    pub question_id: Option<u64>,
+    pub question: String,
+    pub kind: FindingKind,
+    pub md: Option<String>,
+    pub sh: Option<String>,
+    /// The corresponding value in the English questions, for translation checks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub english: Option<String>,
+    /// The difference is accepted by an entry of the suppression file.
+    pub suppressed: bool,
+    /// Byte range of the `md` text in `file`, if the difference can be fixed by replacing it
+    /// with the `sh` text.
+    #[serde(skip)]
+    pub span: Option<Range<usize>>,
+}
+
+/// Collects the findings of `check` over several files.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub files: Vec<PathBuf>,
+    pub findings: Vec<Finding>,
+    /// Suppressions that no longer match any finding.
+    pub unused_suppressions: Vec<Suppression>,
+}
+
+impl Report {
+    /// Compares the questions parsed from `source` (the contents of `path`) with the questions
+    /// on SurveyHero and records every difference.
+    pub fn check_file(
+        &mut self,
+        path: &Path,
+        source: &str,
+        md_questions: &[markdown::Question],
+        sh_questions: &[api::Question],
+        normalizer: &Normalizer,
+    ) {
+        self.files.push(path.to_owned());
+
+        let finding =
+            |question: &str, online: Option<&api::Question>, kind, fragment: &str| Finding {
+                file: path.to_owned(),
+                line: markdown::line_number(source, fragment),
+                question_id: online.map(|q| q.id()),
+                question: question.to_owned(),
+                kind,
+                md: None,
+                sh: None,
+                english: None,
+                suppressed: false,
+                span: None,
+            };
+
+        for (markdown, online) in md_questions.iter().zip(sh_questions.iter()) {
+            for comparison in markdown.compare_all(online, normalizer) {
+                match comparison {
+                    Comparison::Equal => {}
+                    Comparison::TitlesDiffer { md, sh } => self.findings.push(Finding {
+                        md: Some(md.to_owned()),
+                        sh: Some(sh),
+                        span: markdown::span(source, md),
+                        ..finding(markdown.text, Some(online), FindingKind::TitleDiffers, md)
+                    }),
+                    Comparison::QuestionTypesDiffer { question, md, sh } => {
+                        self.findings.push(Finding {
+                            md: Some(md.to_string()),
+                            sh: Some(sh.to_string()),
+                            ..finding(
+                                question,
+                                Some(online),
+                                FindingKind::QuestionTypeDiffers,
+                                question,
+                            )
+                        })
+                    }
+                    Comparison::Unsupported { question, kind } => self.findings.push(Finding {
+                        md: Some(QuestionType::from(markdown).to_string()),
+                        sh: Some(kind),
+                        ..finding(
+                            question,
+                            Some(online),
+                            FindingKind::UnsupportedQuestion,
+                            question,
+                        )
+                    }),
+                    Comparison::AnswersDiffer(diffs) => {
+                        for diff in diffs {
+                            self.findings.push(Finding {
+                                md: Some(diff.md.to_owned()),
+                                sh: Some(diff.sh),
+                                span: markdown::span(source, diff.md),
+                                ..finding(
+                                    markdown.text,
+                                    Some(online),
+                                    FindingKind::AnswerDiffers,
+                                    diff.md,
+                                )
+                            });
+                        }
+                    }
+                    Comparison::MatrixAnswersDiffer(diffs) => {
+                        for diff in diffs {
+                            self.findings.push(Finding {
+                                md: Some(diff.md.to_owned()),
+                                sh: Some(diff.sh),
+                                span: markdown::span(source, diff.md),
+                                ..finding(
+                                    markdown.text,
+                                    Some(online),
+                                    FindingKind::MatrixAnswerDiffers,
+                                    diff.md,
+                                )
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        for markdown in md_questions.iter().skip(sh_questions.len()) {
+            self.findings.push(Finding {
+                md: Some(markdown.text.to_owned()),
+                ..finding(
+                    markdown.text,
+                    None,
+                    FindingKind::MissingOnline,
+                    markdown.text,
+                )
+            });
+        }
+        for online in sh_questions.iter().skip(md_questions.len()) {
+            self.findings.push(Finding {
+                file: path.to_owned(),
+                line: None,
+                question_id: Some(online.id()),
+                question: online.text(),
+                kind: FindingKind::MissingInMarkdown,
+                md: None,
+                sh: Some(online.text()),
+                english: None,
+                suppressed: false,
+                span: None,
+            });
+        }
+    }
+
+    /// Marks the findings accepted by `suppressions`, and remembers the suppressions that did not
+    /// match anything.
+    pub fn apply_suppressions(&mut self, suppressions: &Suppressions) {
+        self.unused_suppressions = suppressions.apply(&mut self.findings);
+        for unused in &self.unused_suppressions {
+            log::warn!(
+                "Suppression of {} for question {} no longer matches anything",
+                unused.kind.as_str(),
+                unused.question_id
+            );
+        }
+    }
+
+    /// Returns `true` if any difference between the Markdown files and SurveyHero was found that
+    /// is not suppressed.
+    pub fn has_drift(&self) -> bool {
+        self.findings.iter().any(|f| !f.suppressed)
+    }
+
+    pub fn write(&self, format: Format, out: &mut dyn Write) -> io::Result<()> {
+        match format {
+            Format::Text => self.write_text(out),
+            Format::Json => {
+                serde_json::to_writer_pretty(&mut *out, self)?;
+                writeln!(out)
+            }
+            Format::Junit => self.write_junit(out),
+            Format::Sarif => {
+                serde_json::to_writer_pretty(&mut *out, &self.sarif())?;
+                writeln!(out)
+            }
+        }
+    }
+
+    fn findings_for<'a>(&'a self, file: &'a Path) -> impl Iterator<Item = &'a Finding> + 'a {
+        self.findings.iter().filter(move |f| f.file == file)
+    }
+
+    fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        for file in &self.files {
+            writeln!(out, "-----\nChecking {}\n", file.display())?;
+            for finding in self.findings_for(file) {
+                match finding.line {
+                    Some(line) => writeln!(
+                        out,
+                        "Q: '{}' ({}:{})",
+                        finding.question,
+                        file.display(),
+                        line
+                    )?,
+                    None => writeln!(out, "Q: '{}'", finding.question)?,
+                }
+                if finding.suppressed {
+                    writeln!(out, "  {} (suppressed)", finding.kind.description())?;
+                } else {
+                    writeln!(out, "  {}", finding.kind.description())?;
+                }
+                if let Some(md) = &finding.md {
+                    writeln!(out, "    md: {md:?}")?;
+                }
+                if let Some(sh) = &finding.sh {
+                    writeln!(out, "    sh: {sh:?}")?;
+                }
+                if let Some(english) = &finding.english {
+                    writeln!(out, "    en: {english:?}")?;
+                }
+            }
+        }
+        writeln!(out, "-----")?;
+        for unused in &self.unused_suppressions {
+            writeln!(
+                out,
+                "warning: suppression of {} for question {}{} no longer matches anything",
+                unused.kind.as_str(),
+                unused.question_id,
+                unused
+                    .file
+                    .as_ref()
+                    .map(|f| format!(" in {}", f.display()))
+                    .unwrap_or_default()
+            )?;
+        }
+        let suppressed = self.findings.iter().filter(|f| f.suppressed).count();
+        writeln!(
+            out,
+            "{} difference(s) found in {} file(s), {} suppressed",
+            self.findings.len() - suppressed,
+            self.files.len(),
+            suppressed
+        )
+    }
+
+    fn write_junit(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            out,
+            r#"<testsuites name="surveyhero-verifier" tests="{}" failures="{}" skipped="{}">"#,
+            self.files
+                .iter()
+                .map(|f| self.findings_for(f).count().max(1))
+                .sum::<usize>(),
+            self.findings.iter().filter(|f| !f.suppressed).count(),
+            self.findings.iter().filter(|f| f.suppressed).count(),
+        )?;
+        for file in &self.files {
+            let name = xml_escape(&file.display().to_string());
+            let tests = self.findings_for(file).count();
+            let skipped = self.findings_for(file).filter(|f| f.suppressed).count();
+            writeln!(
+                out,
+                r#"  <testsuite name="{name}" tests="{}" failures="{}" skipped="{skipped}">"#,
+                tests.max(1),
+                tests - skipped
+            )?;
+            if tests == 0 {
+                writeln!(
+                    out,
+                    r#"    <testcase classname="{name}" name="matches SurveyHero"/>"#
+                )?;
+            }
+            for finding in self.findings_for(file) {
+                writeln!(
+                    out,
+                    r#"    <testcase classname="{name}" name="{}">"#,
+                    xml_escape(&finding.question)
+                )?;
+                if finding.suppressed {
+                    writeln!(
+                        out,
+                        r#"      <skipped message="suppressed: {}"/>"#,
+                        xml_escape(finding.kind.description())
+                    )?;
+                } else {
+                    writeln!(
+                        out,
+                        r#"      <failure type="{}" message="{}">{}</failure>"#,
+                        finding.kind.as_str(),
+                        xml_escape(finding.kind.description()),
+                        xml_escape(&finding.details())
+                    )?;
+                }
+                writeln!(out, "    </testcase>")?;
+            }
+            writeln!(out, "  </testsuite>")?;
+        }
+        writeln!(out, "</testsuites>")
+    }
+
+    fn sarif(&self) -> serde_json::Value {
+        let rules = FindingKind::ALL
+            .iter()
+            .map(|kind| {
+                serde_json::json!({
+                    "id": kind.as_str(),
+                    "shortDescription": { "text": kind.description() },
+                })
+            })
+            .collect::<Vec<_>>();
+        let results = self
+            .findings
+            .iter()
+            .map(|finding| {
+                let mut region = serde_json::Map::new();
+                if let Some(line) = finding.line {
+                    region.insert("startLine".to_owned(), line.into());
+                }
+                let suppressions = if finding.suppressed {
+                    serde_json::json!([{ "kind": "external" }])
+                } else {
+                    serde_json::json!([])
+                };
+                serde_json::json!({
+                    "ruleId": finding.kind.as_str(),
+                    "level": "error",
+                    "message": {
+                        "text": format!("{}: {}\n{}", finding.kind.description(), finding.question, finding.details()),
+                    },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": repo_path(&finding.file) },
+                            "region": region,
+                        },
+                    }],
+                    "properties": {
+                        "questionId": finding.question_id,
+                        "md": finding.md,
+                        "sh": finding.sh,
+                        "en": finding.english,
+                    },
+                    "suppressions": suppressions,
+                })
+            })
+            .collect::<Vec<_>>();
+        let notifications = self
+            .unused_suppressions
+            .iter()
+            .map(|unused| {
+                serde_json::json!({
+                    "level": "warning",
+                    "message": {
+                        "text": format!(
+                            "suppression of {} for question {} no longer matches anything",
+                            unused.kind.as_str(),
+                            unused.question_id
+                        ),
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "surveyhero-verifier",
+                        "informationUri": "https://github.com/rust-lang/surveys",
+                        "rules": rules,
+                    },
+                },
+                "invocations": [{
+                    "executionSuccessful": true,
+                    "toolExecutionNotifications": notifications,
+                }],
+                "results": results,
+            }],
+        })
+    }
+}
+
+impl Finding {
+    fn details(&self) -> String {
+        let mut details = Vec::new();
+        if let Some(id) = self.question_id {
+            details.push(format!("question id: {id}"));
+        }
+        if let Some(md) = &self.md {
+            details.push(format!("md: {md}"));
+        }
+        if let Some(sh) = &self.sh {
+            details.push(format!("sh: {sh}"));
+        }
+        if let Some(english) = &self.english {
+            details.push(format!("en: {english}"));
+        }
+        details.join("\n")
+    }
+}
+
+/// The verifier works with paths relative to the `surveyhero` directory (`../surveys/...`), while
+/// SARIF consumers and `git apply` expect paths relative to the repository root.
+pub(crate) fn repo_path(path: &Path) -> String {
+    path.components()
+        .filter(|c| !matches!(c, Component::ParentDir | Component::CurDir))
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(kind: FindingKind, suppressed: bool) -> Finding {
+        Finding {
+            file: PathBuf::from("../surveys/2024/questions.md"),
+            line: Some(3),
+            question_id: Some(7),
+            question: "Do you use <Rust> & \"Cargo\"?".to_owned(),
+            kind,
+            md: Some("Yes".to_owned()),
+            sh: Some("Yes, always".to_owned()),
+            english: None,
+            suppressed,
+            span: None,
+        }
+    }
+
+    fn report() -> Report {
+        Report {
+            files: vec![
+                PathBuf::from("../surveys/2024/questions.md"),
+                PathBuf::from("../surveys/2024/other.md"),
+            ],
+            findings: vec![
+                finding(FindingKind::AnswerDiffers, false),
+                finding(FindingKind::TitleDiffers, true),
+            ],
+            unused_suppressions: vec![],
+        }
+    }
+
+    fn write(report: &Report, format: Format) -> String {
+        let mut out = Vec::new();
+        report.write(format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn writes_junit() {
+        let junit = write(&report(), Format::Junit);
+        assert!(
+            junit.contains(
+                r#"<testsuites name="surveyhero-verifier" tests="3" failures="1" skipped="1">"#
+            ),
+            "{}",
+            junit
+        );
+        assert!(
+            junit.contains(
+                r#"<testsuite name="../surveys/2024/questions.md" tests="2" failures="1" skipped="1">"#
+            ),
+            "{}",
+            junit
+        );
+        assert!(
+            junit.contains(r#"<testcase classname="../surveys/2024/questions.md" name="Do you use &lt;Rust&gt; &amp; &quot;Cargo&quot;?">"#),
+            "{}",
+            junit
+        );
+        assert!(
+            junit.contains(
+                r#"<failure type="answer-differs" message="answer differs">question id: 7"#
+            ),
+            "{}",
+            junit
+        );
+        assert!(
+            junit.contains(r#"<skipped message="suppressed: question text differs"/>"#),
+            "{}",
+            junit
+        );
+        assert!(
+            junit.contains(
+                r#"<testcase classname="../surveys/2024/other.md" name="matches SurveyHero"/>"#
+            ),
+            "{}",
+            junit
+        );
+    }
+
+    #[test]
+    fn writes_sarif() {
+        let sarif: serde_json::Value =
+            serde_json::from_str(&write(&report(), Format::Sarif)).unwrap();
+        let run = &sarif["runs"][0];
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+            FindingKind::ALL.len()
+        );
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "answer-differs");
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(
+            location["artifactLocation"]["uri"],
+            "surveys/2024/questions.md"
+        );
+        assert_eq!(location["region"]["startLine"], 3);
+        assert_eq!(results[0]["properties"]["questionId"], 7);
+        assert_eq!(results[0]["suppressions"], serde_json::json!([]));
+        assert_eq!(
+            results[1]["suppressions"],
+            serde_json::json!([{ "kind": "external" }])
+        );
+    }
+
+    #[test]
+    fn drift_ignores_suppressed_findings() {
+        let mut report = report();
+        assert!(report.has_drift());
+        report.findings.retain(|f| f.suppressed);
+        assert!(!report.has_drift());
+    }
+
+    #[test]
+    fn every_kind_is_listed() {
+        for kind in FindingKind::ALL {
+            let json = serde_json::to_value(kind).unwrap();
+            assert_eq!(json, kind.as_str());
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use crate::api::Response;
+use crate::error::{Error, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::PathBuf;
+
+/// Local store of downloaded survey responses: one JSON Lines file per survey, with one
+/// response per line.
+///
+/// Responses contain personal data, so the store must never be committed to the repository.
+#[derive(Debug, Clone)]
+pub struct ResponseStore {
+    dir: PathBuf,
+}
+
+impl ResponseStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The file holding the responses of `survey_id`.
+    pub fn path(&self, survey_id: usize) -> PathBuf {
+        self.dir.join(format!("{survey_id}.jsonl"))
+    }
+
+    /// Replaces the stored responses of `survey_id`. The file is written next to the old one
+    /// and then renamed, so an interrupted download never leaves a truncated store.
+    pub fn write_all(&self, survey_id: usize, responses: &[Response]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).map_err(Error::io(&self.dir))?;
+        let path = self.path(survey_id);
+        let tmp_path = path.with_extension("jsonl.tmp");
+
+        let write = || -> std::io::Result<()> {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(&tmp_path)?);
+            for response in responses {
+                serde_json::to_writer(&mut file, response)?;
+                writeln!(file)?;
+            }
+            file.flush()
+        };
+        write().map_err(Error::io(&tmp_path))?;
+        std::fs::rename(&tmp_path, &path).map_err(Error::io(&path))
+    }
+
+    /// Adds `response` to the stored responses of `survey_id`.
+    pub fn append(&self, survey_id: usize, response: &Response) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).map_err(Error::io(&self.dir))?;
+        let path = self.path(survey_id);
+        serde_json::to_string(response)
+            .map_err(std::io::Error::from)
+            .and_then(|line| {
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)?
+                    .write_all(format!("{line}\n").as_bytes())
+            })
+            .map_err(Error::io(&path))
+    }
+
+    /// Reads the stored responses of `survey_id`; an empty list if nothing has been stored yet.
+    pub fn load(&self, survey_id: usize) -> Result<Vec<Response>> {
+        let path = self.path(survey_id);
+        let file = match std::fs::File::open(&path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => return Err(Error::io(&path)(error)),
+        };
+        BufReader::new(file)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|(index, line)| {
+                let line = line.map_err(Error::io(&path))?;
+                serde_json::from_str(&line).map_err(|source| Error::Parse {
+                    path: path.clone(),
+                    line: Some(index + 1),
+                    source,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Counts the answers of `responses` that have a question kind the client does not know.
+pub fn unknown_answers(responses: &[Response]) -> usize {
+    responses
+        .iter()
+        .flat_map(|r| &r.answers)
+        .filter(|a| a.question_id().is_none())
+        .count()
+}
+
+/// Running totals of the responses to a survey.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResponseTotals {
+    pub responses: u64,
+    pub completed: u64,
+    /// Number of responses per language code.
+    pub per_language: BTreeMap<String, u64>,
+    /// Number of responses that answered a question, by question ID.
+    pub per_question: BTreeMap<u64, u64>,
+}
+
+impl ResponseTotals {
+    pub fn new(responses: &[Response]) -> Self {
+        let mut totals = Self::default();
+        for response in responses {
+            totals.add(response);
+        }
+        totals
+    }
+
+    pub fn add(&mut self, response: &Response) {
+        self.responses += 1;
+        if response.status == "completed" {
+            self.completed += 1;
+        }
+        let language = response.language.as_deref().unwrap_or("unknown");
+        *self.per_language.entry(language.to_owned()).or_default() += 1;
+        for question_id in response.answers.iter().filter_map(|a| a.question_id()) {
+            *self.per_question.entry(question_id).or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use serde_json::json;
+
+    fn responses() -> Vec<Response> {
+        serde_json::from_value(json!([
+            {
+                "response_id": 1,
+                "status": "completed",
+                "language": "en",
+                "answers": [
+                    { "type": "choice_list", "question_id": 1, "choices": [{ "choice_id": 11 }] },
+                    { "type": "input", "question_id": 2, "text": "Speed" },
+                ],
+            },
+            {
+                "response_id": 2,
+                "status": "in_progress",
+                "answers": [
+                    { "type": "choice_list", "question_id": 1, "choices": [{ "choice_id": 12 }] },
+                    { "type": "slider", "question_id": 4, "value": 3 },
+                ],
+            },
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn writes_appends_and_loads_responses() {
+        let dir = TempDir::new("responses");
+        let store = ResponseStore::new(dir.path());
+        assert!(store.load(7).unwrap().is_empty());
+
+        let mut responses = responses();
+        let last = responses.pop().unwrap();
+        store.write_all(7, &responses).unwrap();
+        store.append(7, &last).unwrap();
+
+        let loaded = store.load(7).unwrap();
+        assert_eq!(
+            loaded.iter().map(|r| r.response_id).collect::<Vec<_>>(),
+            [1, 2]
+        );
+        assert!(!dir.join("7.jsonl.tmp").exists());
+
+        // A new download replaces the stored responses
+        store.write_all(7, &responses).unwrap();
+        assert_eq!(store.load(7).unwrap().len(), 1);
+
+        let mut text = std::fs::read_to_string(store.path(7)).unwrap();
+        text.push_str("{\"response_id\": \n");
+        std::fs::write(store.path(7), text).unwrap();
+        assert!(matches!(
+            store.load(7),
+            Err(Error::Parse { line: Some(2), .. })
+        ));
+    }
+
+    #[test]
+    fn counts_totals() {
+        let responses = responses();
+        assert_eq!(unknown_answers(&responses), 1);
+
+        let totals = ResponseTotals::new(&responses);
+        assert_eq!(totals.responses, 2);
+        assert_eq!(totals.completed, 1);
+        assert_eq!(
+            totals.per_language,
+            BTreeMap::from([("en".to_owned(), 1), ("unknown".to_owned(), 1)])
+        );
+        assert_eq!(totals.per_question, BTreeMap::from([(1, 2), (2, 1)]));
+    }
+}
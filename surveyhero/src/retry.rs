@@ -0,0 +1,182 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use std::time::{Duration, SystemTime};
+
+/// When and how long to wait before repeating a failed request.
+///
+/// Rate limited (429) and server error (5xx) responses as well as connection errors and
+/// timeouts are retried with exponential backoff and full jitter: before attempt `n + 1`, the
+/// client waits a random time between zero and `base_delay * 2^(n - 1)`, capped at `max_delay`.
+/// A `Retry-After` header sent by SurveyHero takes precedence over the computed delay, but is
+/// capped at `max_delay` as well.
+///
+/// Requests that are not idempotent (`POST`) may have succeeded even if the response is an error
+/// or never arrives, so they are only retried when SurveyHero rate limited them or the
+/// connection could not be established.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total number of attempts per request, including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The default policy, with the number of attempts taken from `SH_API_MAX_ATTEMPTS` if it
+    /// is set.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(value) = std::env::var("SH_API_MAX_ATTEMPTS") {
+            match value.parse::<u32>() {
+                Ok(attempts) if attempts > 0 => policy.max_attempts = attempts,
+                _ => log::warn!(
+                    "Ignoring invalid SH_API_MAX_ATTEMPTS={value:?}, using {} attempts",
+                    policy.max_attempts
+                ),
+            }
+        }
+        policy
+    }
+
+    /// Whether a response with `status` to a `method` request is worth another attempt.
+    pub fn is_retryable(method: &Method, status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS
+            || (is_idempotent(method) && status.is_server_error())
+    }
+
+    /// How long to wait after the failed attempt number `attempt` (starting at 1).
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        backoff.mul_f64(fastrand::f64())
+    }
+}
+
+/// Whether sending a `method` request twice has the same effect as sending it once.
+pub fn is_idempotent(method: &Method) -> bool {
+    method != Method::POST && method != Method::PATCH
+}
+
+/// Parses a `Retry-After` header, given either in seconds or as an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn backs_off_exponentially_with_jitter() {
+        let policy = policy();
+        for (attempt, max) in [
+            (1, 100),
+            (2, 200),
+            (3, 400),
+            (4, 800),
+            (5, 1000),
+            (40, 1000),
+        ] {
+            for _ in 0..20 {
+                let delay = policy.delay(attempt, None);
+                assert!(
+                    delay <= Duration::from_millis(max),
+                    "attempt {}: {:?}",
+                    attempt,
+                    delay
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn follows_retry_after_up_to_the_maximum_delay() {
+        let policy = policy();
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_millis(700))),
+            Duration::from_millis(700)
+        );
+        assert_eq!(policy.delay(3, Some(Duration::ZERO)), Duration::ZERO);
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(60))),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn parses_retry_after() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+        assert_eq!(retry_after(&headers("120")), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after(&headers("soon")), None);
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::ZERO)
+        );
+
+        let future = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+        let delay = retry_after(&headers(&future)).unwrap();
+        assert!(
+            delay > Duration::from_secs(28) && delay <= Duration::from_secs(30),
+            "{:?}",
+            delay
+        );
+    }
+
+    #[test]
+    fn retries_rate_limits_and_server_errors() {
+        let retryable = RetryPolicy::is_retryable;
+        assert!(retryable(&Method::GET, StatusCode::TOO_MANY_REQUESTS));
+        assert!(retryable(&Method::GET, StatusCode::SERVICE_UNAVAILABLE));
+        assert!(retryable(&Method::PUT, StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!retryable(&Method::GET, StatusCode::NOT_FOUND));
+        assert!(!retryable(&Method::GET, StatusCode::UNAUTHORIZED));
+        // A POST that failed on the server may have created something already
+        assert!(retryable(&Method::POST, StatusCode::TOO_MANY_REQUESTS));
+        assert!(!retryable(&Method::POST, StatusCode::SERVICE_UNAVAILABLE));
+    }
+}
@@ -0,0 +1,275 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// An HTTP request received by a [`Server`].
+#[derive(Debug)]
+pub(crate) struct Request {
+    pub method: String,
+    /// Path and query.
+    pub path: String,
+    /// Header names are lowercase.
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// A minimal HTTP/1.1 server answering every request with JSON, used by the webhook receiver.
+/// Connections are handled by a fixed number of worker threads and closed after one request;
+/// while all workers are busy, new connections wait in the listen backlog. Slow clients time out
+/// and oversized requests are rejected. The server stops when it is dropped.
+pub(crate) struct Server {
+    address: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    /// Starts answering the requests to `listener` with `handler`, which returns the status
+    /// and the JSON body of the response. `name` is used in log messages.
+    pub fn start<H>(listener: TcpListener, name: &'static str, handler: H) -> io::Result<Self>
+    where
+        H: Fn(Request) -> (u16, Value) + Send + Sync + 'static,
+    {
+        let address = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handler = Arc::new(handler);
+
+        // Accepting blocks while every worker is busy, the workers stop once the sender is dropped
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(0);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..WORKERS {
+            let (receiver, handler) = (receiver.clone(), handler.clone());
+            std::thread::spawn(move || loop {
+                let Ok(stream) = receiver.lock().unwrap().recv() else {
+                    break;
+                };
+                if let Err(error) = handle_connection(stream, &*handler) {
+                    log::warn!("{name} connection failed: {error}");
+                }
+            });
+        }
+
+        let thread_shutdown = shutdown.clone();
+        let thread = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                if sender.send(stream).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            address,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Blocks until the server stops, which only happens if accepting connections fails.
+    pub fn join(mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake up the accept loop
+        let _ = TcpStream::connect(self.address);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Number of connections handled at the same time.
+const WORKERS: usize = 4;
+/// How long a client may take to send its request or to receive the response.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum size of the request line and headers.
+const MAX_HEAD_LENGTH: u64 = 16 * 1024;
+/// Maximum size of a request body.
+const MAX_BODY_LENGTH: u64 = 1024 * 1024;
+
+fn handle_connection(
+    stream: TcpStream,
+    handler: &dyn Fn(Request) -> (u16, Value),
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?).take(MAX_HEAD_LENGTH);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(());
+    };
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') {
+            return write_response(stream, 431, &json!({ "error": "headers too large" }));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_owned());
+        }
+    }
+    let length = match headers.get("content-length").map(|l| l.parse::<u64>()) {
+        None => 0,
+        Some(Ok(length)) if length <= MAX_BODY_LENGTH => length,
+        Some(Ok(_)) => return write_response(stream, 413, &json!({ "error": "body too large" })),
+        Some(Err(_)) => return write_response(stream, 400, &json!({ "error": "invalid length" })),
+    };
+    let mut body = vec![0; length as usize];
+    reader.set_limit(length);
+    reader.read_exact(&mut body)?;
+
+    let (status, body) = handler(Request {
+        method: method.to_owned(),
+        path: path.to_owned(),
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    });
+    write_response(stream, status, &body)
+}
+
+fn write_response(mut stream: TcpStream, status: u16, body: &Value) -> io::Result<()> {
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {status} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        reason(status),
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        413 => "Content Too Large",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start() -> Server {
+        Server::start(
+            TcpListener::bind("127.0.0.1:0").unwrap(),
+            "Test",
+            |request| (200, json!({ "length": request.body.len() })),
+        )
+        .unwrap()
+    }
+
+    fn send(server: &Server, request: &[u8]) -> String {
+        let mut stream = TcpStream::connect(server.address()).unwrap();
+        stream.write_all(request).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn answers_requests() {
+        let response = send(
+            &start(),
+            b"POST /webhook HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.ends_with(r#"{"length":2}"#), "{}", response);
+    }
+
+    #[test]
+    fn limits_concurrent_connections() {
+        let (running, most) = (Arc::new(Mutex::new(0)), Arc::new(Mutex::new(0)));
+        let (handler_running, handler_most) = (running.clone(), most.clone());
+        let server = Server::start(
+            TcpListener::bind("127.0.0.1:0").unwrap(),
+            "Test",
+            move |_| {
+                {
+                    let mut running = handler_running.lock().unwrap();
+                    *running += 1;
+                    let mut most = handler_most.lock().unwrap();
+                    *most = (*most).max(*running);
+                }
+                std::thread::sleep(Duration::from_millis(50));
+                *handler_running.lock().unwrap() -= 1;
+                (200, json!({}))
+            },
+        )
+        .unwrap();
+
+        std::thread::scope(|scope| {
+            let clients = (0..WORKERS * 2)
+                .map(|_| scope.spawn(|| send(&server, b"GET / HTTP/1.1\r\n\r\n")))
+                .collect::<Vec<_>>();
+            for client in clients {
+                let response = client.join().unwrap();
+                assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+            }
+        });
+        assert!(*most.lock().unwrap() <= WORKERS);
+        assert_eq!(*running.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_oversized_requests() {
+        let server = start();
+        let response = send(
+            &server,
+            format!(
+                "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                MAX_BODY_LENGTH + 1
+            )
+            .as_bytes(),
+        );
+        assert!(response.starts_with("HTTP/1.1 413 "), "{}", response);
+
+        // The server closes the connection without reading the whole request, which may reset
+        // it before the response arrives
+        let header = format!(
+            "GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(20_000)
+        );
+        let mut stream = TcpStream::connect(server.address()).unwrap();
+        let _ = stream.write_all(header.as_bytes());
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        assert!(
+            response.is_empty() || response.starts_with("HTTP/1.1 431 "),
+            "{}",
+            response
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use crate::api::{Condition, ConditionAction};
+use crate::error::{Error, Result};
+use crate::patch::unified_diff;
+use crate::render::{render_markdown, render_survey_markdown};
+use crate::SurveyData;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The survey contents downloaded from SurveyHero at one point in time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub survey_id: usize,
+    /// Unix timestamp (in seconds) of the download.
+    pub taken_at: u64,
+    pub data: SurveyData,
+}
+
+impl Snapshot {
+    /// A snapshot of `data`, taken now.
+    pub fn new(survey_id: usize, data: SurveyData) -> Self {
+        Self {
+            survey_id,
+            taken_at: now(),
+            data,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(Error::io(path))?;
+        serde_json::from_str(&text).map_err(|source| Error::Parse {
+            path: path.to_owned(),
+            line: None,
+            source,
+        })
+    }
+
+    /// Time since the snapshot was taken.
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now().saturating_sub(self.taken_at))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Local store of survey snapshots: `<dir>/<survey_id>/<taken_at>.json`, or
+/// `<taken_at>-<n>.json` for further snapshots taken in the same second.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Writes `snapshot` to the store and returns its path. Existing snapshots are never
+    /// overwritten.
+    pub fn save(&self, snapshot: &Snapshot) -> Result<PathBuf> {
+        let dir = self.dir.join(snapshot.survey_id.to_string());
+        std::fs::create_dir_all(&dir).map_err(Error::io(&dir))?;
+        let tmp_path = dir.join(format!("{}.{}.tmp", snapshot.taken_at, std::process::id()));
+        serde_json::to_string(snapshot)
+            .map_err(std::io::Error::from)
+            .and_then(|text| std::fs::write(&tmp_path, text))
+            .map_err(Error::io(&tmp_path))?;
+        // Linking fails if the name is taken, so concurrent saves cannot replace each other
+        let mut number = 0;
+        let result = loop {
+            let name = match number {
+                0 => format!("{}.json", snapshot.taken_at),
+                n => format!("{}-{n}.json", snapshot.taken_at),
+            };
+            let path = dir.join(name);
+            match std::fs::hard_link(&tmp_path, &path) {
+                Ok(()) => break Ok(path),
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => number += 1,
+                Err(error) => break Err(Error::io(&path)(error)),
+            }
+        };
+        std::fs::remove_file(&tmp_path).map_err(Error::io(&tmp_path))?;
+        result
+    }
+
+    /// Paths of the snapshots of `survey_id`, from the oldest to the newest.
+    pub fn list(&self, survey_id: usize) -> Result<Vec<PathBuf>> {
+        let dir = self.dir.join(survey_id.to_string());
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => return Err(Error::io(&dir)(error)),
+        };
+        let mut snapshots = vec![];
+        for entry in entries {
+            let path = entry.map_err(Error::io(&dir))?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                if let Some(key) = sort_key(&path) {
+                    snapshots.push((key, path));
+                }
+            }
+        }
+        snapshots.sort();
+        Ok(snapshots.into_iter().map(|(_, path)| path).collect())
+    }
+
+    /// The newest snapshot of `survey_id`, if there is one.
+    pub fn latest(&self, survey_id: usize) -> Result<Option<Snapshot>> {
+        self.list(survey_id)?
+            .last()
+            .map(|path| Snapshot::load(path))
+            .transpose()
+    }
+
+    /// The newest snapshot of `survey_id` if it is at most `max_age` old.
+    pub fn latest_within(&self, survey_id: usize, max_age: Duration) -> Result<Option<Snapshot>> {
+        Ok(self
+            .latest(survey_id)?
+            .filter(|snapshot| snapshot.age() <= max_age))
+    }
+}
+
+/// The timestamp and the number of a snapshot file name, see [`SnapshotStore`].
+fn sort_key(path: &Path) -> Option<(u64, u64)> {
+    let stem = path.file_stem()?.to_str()?;
+    match stem.split_once('-') {
+        Some((taken_at, number)) => Some((taken_at.parse().ok()?, number.parse().ok()?)),
+        None => Some((stem.parse().ok()?, 0)),
+    }
+}
+
+/// A unified diff of what changed on SurveyHero between two snapshots: the Markdown rendering
+/// of every language and the skip logic. Empty if nothing changed.
+pub fn diff(old: &Snapshot, new: &Snapshot) -> String {
+    let mut diff = String::new();
+    let mut push = |name: &str, old: &str, new: &str| {
+        if old != new {
+            diff.push_str(&unified_diff(name, old, new));
+        }
+    };
+
+    push(
+        "questions.md",
+        &render_survey_markdown(&old.data.elements),
+        &render_survey_markdown(&new.data.elements),
+    );
+    let mut languages = old
+        .data
+        .secondary_languages
+        .iter()
+        .chain(&new.data.secondary_languages)
+        .map(|(language, _)| language.as_str())
+        .collect::<Vec<_>>();
+    languages.sort();
+    languages.dedup();
+    for language in languages {
+        let render = |data: &SurveyData| {
+            data.secondary_languages
+                .iter()
+                .find(|(l, _)| l == language)
+                .map(|(_, questions)| render_markdown(questions))
+                .unwrap_or_default()
+        };
+        push(
+            &format!("translations/{language}.md"),
+            &render(&old.data),
+            &render(&new.data),
+        );
+    }
+    push(
+        "conditions",
+        &render_conditions(&old.data.conditions),
+        &render_conditions(&new.data.conditions),
+    );
+    diff
+}
+
+/// One line per skip logic rule.
+fn render_conditions(conditions: &[Condition]) -> String {
+    conditions
+        .iter()
+        .map(|condition| {
+            let choice = condition
+                .choice_id
+                .map(|id| format!(" choice {id}"))
+                .unwrap_or_default();
+            let action = match &condition.action {
+                ConditionAction::JumpToQuestion { question_id } => {
+                    format!("jump to question {question_id}")
+                }
+                ConditionAction::EndSurvey => "end survey".to_owned(),
+                ConditionAction::Unknown => "unknown action".to_owned(),
+            };
+            format!("question {}{choice}: {action}\n", condition.question_id)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn snapshot(taken_at: u64, conditions: usize) -> Snapshot {
+        let condition = serde_json::json!({
+            "question_id": 1,
+            "action": { "type": "end_survey" },
+        });
+        Snapshot {
+            survey_id: 42,
+            taken_at,
+            data: SurveyData {
+                main: vec![],
+                secondary_languages: vec![],
+                conditions: (0..conditions)
+                    .map(|_| serde_json::from_value(condition.clone()).unwrap())
+                    .collect(),
+                elements: vec![],
+            },
+        }
+    }
+
+    fn file_name(path: &Path) -> &str {
+        path.file_name().unwrap().to_str().unwrap()
+    }
+
+    #[test]
+    fn names_snapshots_taken_in_the_same_second() {
+        let dir = TempDir::new("snapshot");
+        let store = SnapshotStore::new(dir.path());
+        assert!(store.list(42).unwrap().is_empty());
+        assert!(store.latest(42).unwrap().is_none());
+
+        let paths = [
+            store.save(&snapshot(20, 0)).unwrap(),
+            store.save(&snapshot(20, 1)).unwrap(),
+            store.save(&snapshot(3, 0)).unwrap(),
+            store.save(&snapshot(20, 2)).unwrap(),
+        ];
+        assert_eq!(
+            paths.iter().map(|p| file_name(p)).collect::<Vec<_>>(),
+            ["20.json", "20-1.json", "3.json", "20-2.json"]
+        );
+        // Ordered by time and then by number, temporary files are removed
+        let listed = store.list(42).unwrap();
+        assert_eq!(
+            listed.iter().map(|p| file_name(p)).collect::<Vec<_>>(),
+            ["3.json", "20.json", "20-1.json", "20-2.json"]
+        );
+        assert_eq!(std::fs::read_dir(dir.join("42")).unwrap().count(), 4);
+        assert_eq!(store.latest(42).unwrap().unwrap().data.conditions.len(), 2);
+    }
+
+    #[test]
+    fn selects_the_newest_snapshot_by_age() {
+        let dir = TempDir::new("snapshot");
+        let store = SnapshotStore::new(dir.path());
+        let hour = 60 * 60;
+        store.save(&snapshot(now() - 2 * hour, 0)).unwrap();
+        assert!(store
+            .latest_within(42, Duration::from_secs(hour))
+            .unwrap()
+            .is_none());
+        let latest = store
+            .latest_within(42, Duration::from_secs(3 * hour))
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.taken_at, now() - 2 * hour);
+
+        store.save(&snapshot(now() - 60, 1)).unwrap();
+        let latest = store
+            .latest_within(42, Duration::from_secs(hour))
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.data.conditions.len(), 1);
+        assert!(latest.age() >= Duration::from_secs(60));
+        // Other surveys have their own snapshots
+        assert!(store.latest(7).unwrap().is_none());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::config::{read_optional, survey_file};
+use crate::error::{Error, Result};
+use crate::report::{Finding, FindingKind};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// An accepted difference between the repository and SurveyHero.
+///
+/// Suppressions are read from `suppressions.toml` in the survey directory (or
+/// `<name>.suppressions.toml` next to a single-file survey):
+///
+/// ```toml
+/// [[suppression]]
+/// question_id = 123456789
+/// kind = "answer-differs"
+/// file = "translations/de.md"
+/// reason = "Wording approved by the German translators"
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Suppression {
+    /// SurveyHero ID of the question.
+    pub question_id: u64,
+    pub kind: FindingKind,
+    /// Restricts the suppression to a single file, relative to the survey directory. Without it,
+    /// the suppression applies to every language.
+    pub file: Option<PathBuf>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Suppressions {
+    /// Directory the `file` entries are relative to.
+    base: PathBuf,
+    entries: Vec<Suppression>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SuppressionFile {
+    #[serde(default)]
+    suppression: Vec<Suppression>,
+}
+
+impl Suppressions {
+    pub fn load(survey_path: &Path) -> Result<Self> {
+        let base = if survey_path.is_dir() {
+            survey_path.to_owned()
+        } else {
+            survey_path.parent().unwrap_or(Path::new("")).to_owned()
+        };
+        let path = survey_file(survey_path, "suppressions.toml");
+        let Some(text) = read_optional(&path)? else {
+            return Ok(Self {
+                base,
+                entries: vec![],
+            });
+        };
+        let file: SuppressionFile = toml::from_str(&text).map_err(|error| Error::Config {
+            path,
+            message: error.to_string(),
+        })?;
+        Ok(Self {
+            base,
+            entries: file.suppression,
+        })
+    }
+
+    /// Marks every finding matched by a suppression as suppressed and returns the suppressions
+    /// that did not match anything.
+    pub fn apply(&self, findings: &mut [Finding]) -> Vec<Suppression> {
+        let mut used = vec![false; self.entries.len()];
+        for finding in findings {
+            for (entry, used) in self.entries.iter().zip(used.iter_mut()) {
+                if self.matches(entry, finding) {
+                    finding.suppressed = true;
+                    *used = true;
+                }
+            }
+        }
+        self.entries
+            .iter()
+            .zip(used)
+            .filter(|(_, used)| !used)
+            .map(|(entry, _)| entry.clone())
+            .collect()
+    }
+
+    fn matches(&self, entry: &Suppression, finding: &Finding) -> bool {
+        finding.question_id == Some(entry.question_id)
+            && finding.kind == entry.kind
+            && entry
+                .file
+                .as_ref()
+                .map(|file| self.base.join(file) == finding.file)
+                .unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn finding(file: &str, question_id: u64, kind: FindingKind) -> Finding {
+        Finding {
+            file: PathBuf::from(file),
+            line: None,
+            question_id: Some(question_id),
+            question: "Question".to_owned(),
+            kind,
+            md: None,
+            sh: None,
+            english: None,
+            suppressed: false,
+            span: None,
+        }
+    }
+
+    fn suppression(question_id: u64, kind: FindingKind, file: Option<&str>) -> Suppression {
+        Suppression {
+            question_id,
+            kind,
+            file: file.map(PathBuf::from),
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn matches_question_kind_and_file() {
+        let suppressions = Suppressions {
+            base: PathBuf::from("surveys/2024"),
+            entries: vec![
+                suppression(1, FindingKind::AnswerDiffers, Some("translations/de.md")),
+                suppression(2, FindingKind::TitleDiffers, None),
+            ],
+        };
+        let mut findings = vec![
+            finding(
+                "surveys/2024/translations/de.md",
+                1,
+                FindingKind::AnswerDiffers,
+            ),
+            finding(
+                "surveys/2024/translations/fr.md",
+                1,
+                FindingKind::AnswerDiffers,
+            ),
+            finding(
+                "surveys/2024/translations/de.md",
+                1,
+                FindingKind::TitleDiffers,
+            ),
+            finding("surveys/2024/questions.md", 2, FindingKind::TitleDiffers),
+            finding(
+                "surveys/2024/translations/fr.md",
+                2,
+                FindingKind::TitleDiffers,
+            ),
+        ];
+
+        let unused = suppressions.apply(&mut findings);
+        assert!(unused.is_empty(), "{:?}", unused);
+        let suppressed = findings.iter().map(|f| f.suppressed).collect::<Vec<_>>();
+        assert_eq!(suppressed, [true, false, false, true, true]);
+    }
+
+    #[test]
+    fn returns_suppressions_that_no_longer_match() {
+        let suppressions = Suppressions {
+            base: PathBuf::new(),
+            entries: vec![
+                suppression(1, FindingKind::AnswerDiffers, None),
+                suppression(3, FindingKind::AnswerDiffers, None),
+            ],
+        };
+        let mut findings = vec![finding("questions.md", 1, FindingKind::AnswerDiffers)];
+
+        let unused = suppressions.apply(&mut findings);
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].question_id, 3);
+    }
+
+    #[test]
+    fn loads_suppression_file() {
+        let temp = TempDir::new("suppress");
+        let dir = temp.path();
+
+        let empty = Suppressions::load(dir).unwrap();
+        assert!(empty.entries.is_empty());
+
+        std::fs::write(
+            dir.join("suppressions.toml"),
+            "[[suppression]]\nquestion_id = 5\nkind = \"answer-differs\"\nreason = \"approved\"\n",
+        )
+        .unwrap();
+        let loaded = Suppressions::load(dir).unwrap();
+        assert_eq!(loaded.base, dir);
+        assert_eq!(loaded.entries.len(), 1);
+        assert_eq!(loaded.entries[0].kind, FindingKind::AnswerDiffers);
+
+        std::fs::write(
+            dir.join("suppressions.toml"),
+            "[[suppression]]\nquestion_id = 5\nkind = \"answer-differs\"\nexpires = 1\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            Suppressions::load(dir),
+            Err(Error::Config { path, .. }) if path == dir.join("suppressions.toml")
+        ));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::markdown::{self, Answers};
+use crate::report::{Finding, FindingKind, Report};
+use crate::QuestionType;
+use std::path::Path;
+
+impl Report {
+    /// Compares the structure of a translation (the contents of `path`) with the English
+    /// questions: number and order of questions, question types, number of answers and matrix
+    /// shapes. Texts are not compared, so this works without SurveyHero.
+    pub fn check_translation(
+        &mut self,
+        path: &Path,
+        source: &str,
+        translated: &[markdown::Question],
+        english: &[markdown::Question],
+    ) {
+        self.files.push(path.to_owned());
+
+        let finding = |question: &markdown::Question, kind| Finding {
+            file: path.to_owned(),
+            line: markdown::line_number(source, question.text),
+            question_id: None,
+            question: question.text.to_owned(),
+            kind,
+            md: None,
+            sh: None,
+            english: None,
+            suppressed: false,
+            span: None,
+        };
+
+        for (translation, original) in translated.iter().zip(english) {
+            if let Some((kind, md, en)) = structure_mismatch(translation, original) {
+                self.findings.push(Finding {
+                    md: Some(md),
+                    english: Some(en),
+                    ..finding(translation, kind)
+                });
+            }
+        }
+
+        for original in english.iter().skip(translated.len()) {
+            let kind = FindingKind::MissingInTranslation;
+            // Missing questions are reported at the last translated one, an empty translation has
+            // no line to point to
+            let at = match translated.last() {
+                Some(last) => finding(last, kind),
+                None => Finding {
+                    line: None,
+                    ..finding(original, kind)
+                },
+            };
+            self.findings.push(Finding {
+                english: Some(original.text.to_owned()),
+                ..at
+            });
+        }
+        for translation in translated.iter().skip(english.len()) {
+            self.findings.push(Finding {
+                md: Some(translation.text.to_owned()),
+                ..finding(translation, FindingKind::ExtraInTranslation)
+            });
+        }
+    }
+}
+
+/// Returns the kind of the structural difference between a translated question and its English
+/// original, together with a description of the translated and the English side.
+fn structure_mismatch(
+    translation: &markdown::Question,
+    original: &markdown::Question,
+) -> Option<(FindingKind, String, String)> {
+    let (translated_type, original_type) = (
+        QuestionType::from(translation),
+        QuestionType::from(original),
+    );
+    if translated_type != original_type {
+        return Some((
+            FindingKind::QuestionTypeDiffers,
+            translated_type.to_string(),
+            original_type.to_string(),
+        ));
+    }
+
+    match (&translation.answers, &original.answers) {
+        (
+            Answers::Matrix {
+                answers1: rows1,
+                answers2: columns1,
+                ..
+            },
+            Answers::Matrix {
+                answers1: rows2,
+                answers2: columns2,
+                ..
+            },
+        ) => (rows1.len() != rows2.len() || columns1.len() != columns2.len()).then(|| {
+            (
+                FindingKind::MatrixShapeDiffers,
+                format!("{} rows x {} columns", rows1.len(), columns1.len()),
+                format!("{} rows x {} columns", rows2.len(), columns2.len()),
+            )
+        }),
+        (translated, original) => {
+            let (count1, count2) = (answer_count(translated), answer_count(original));
+            (count1 != count2).then(|| {
+                (
+                    FindingKind::AnswerCountDiffers,
+                    format!("{count1} answer(s)"),
+                    format!("{count2} answer(s)"),
+                )
+            })
+        }
+    }
+}
+
+fn answer_count(answers: &Answers) -> usize {
+    match answers {
+        Answers::SelectOne(a)
+        | Answers::SelectMany(a)
+        | Answers::Ranking(a)
+        | Answers::InputList(a) => a.len(),
+        Answers::Matrix {
+            answers1, answers2, ..
+        } => answers1.len() * answers2.len(),
+        Answers::FreeForm | Answers::RatingScale | Answers::Unsupported => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGLISH: &str = "\
+### Do you use Rust?
+
+Type: select one
+
+- Yes
+- No
+
+### How do you rate these tools?
+
+Type: matrix
+
+Rows:
+
+- Compiler
+- Cargo
+
+Columns:
+
+- Good
+- Bad
+
+### Why?
+
+Type: free form
+";
+
+    fn check(translation: &str) -> Vec<(FindingKind, Option<String>, Option<String>)> {
+        let mut report = Report::default();
+        report.check_translation(
+            Path::new("de.md"),
+            translation,
+            &markdown::parse(translation).unwrap(),
+            &markdown::parse(ENGLISH).unwrap(),
+        );
+        report
+            .findings
+            .into_iter()
+            .map(|f| (f.kind, f.md, f.english))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_matching_structure() {
+        let translation = ENGLISH
+            .replace("Do you use Rust?", "Benutzt du Rust?")
+            .replace("Yes", "Ja")
+            .replace("Cargo", "Cargo (Paketmanager)");
+        assert_eq!(check(&translation), []);
+    }
+
+    #[test]
+    fn reports_answer_count_and_matrix_shape() {
+        let translation = ENGLISH
+            .replace("- No\n", "- Nein\n- Vielleicht\n")
+            .replace("- Bad\n", "");
+        assert_eq!(
+            check(&translation),
+            [
+                (
+                    FindingKind::AnswerCountDiffers,
+                    Some("3 answer(s)".to_owned()),
+                    Some("2 answer(s)".to_owned())
+                ),
+                (
+                    FindingKind::MatrixShapeDiffers,
+                    Some("2 rows x 1 columns".to_owned()),
+                    Some("2 rows x 2 columns".to_owned())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_question_type() {
+        let translation = ENGLISH.replace("select one", "select all that apply");
+        let findings = check(&translation);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].0, FindingKind::QuestionTypeDiffers);
+    }
+
+    #[test]
+    fn reports_missing_and_extra_questions() {
+        let (first_two, _) = ENGLISH.split_once("### Why?").unwrap();
+        assert_eq!(
+            check(first_two),
+            [(
+                FindingKind::MissingInTranslation,
+                None,
+                Some("Why?".to_owned())
+            )]
+        );
+
+        let mut report = Report::default();
+        report.check_translation(
+            Path::new("de.md"),
+            "",
+            &[],
+            &markdown::parse(ENGLISH).unwrap(),
+        );
+        assert_eq!(report.findings.len(), 3);
+        assert!(report
+            .findings
+            .iter()
+            .all(|f| f.kind == FindingKind::MissingInTranslation && f.line.is_none()));
+        assert_eq!(
+            report.findings[0].english.as_deref(),
+            Some("Do you use Rust?")
+        );
+
+        let extra = format!("{ENGLISH}\n### Warum?\n\nType: free form\n");
+        assert_eq!(
+            check(&extra),
+            [(
+                FindingKind::ExtraInTranslation,
+                Some("Warum?".to_owned()),
+                None
+            )]
+        );
+    }
+}
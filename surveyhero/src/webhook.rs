@@ -0,0 +1,215 @@
+use crate::api::Response;
+use crate::error::{Error, Result};
+use crate::responses::{ResponseStore, ResponseTotals};
+use crate::server::{Request, Server};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+
+/// The event SurveyHero sends when a participant submits the survey.
+const RESPONSE_COMPLETED: &str = "response.completed";
+
+/// The header carrying the signature of a callback.
+pub const SIGNATURE_HEADER: &str = "x-surveyhero-signature";
+
+/// Receives the webhook callbacks SurveyHero sends for new responses and appends the responses
+/// to the local [`ResponseStore`], keeping running totals.
+///
+/// SurveyHero is configured to call `POST /webhook` and to sign every callback with the shared
+/// secret: the [`SIGNATURE_HEADER`] holds the hex-encoded HMAC-SHA256 of the body (see
+/// [`signature`]). Callbacks with a missing or wrong signature, for another survey or for a
+/// response that is already stored (SurveyHero retries callbacks that failed) are not stored.
+/// `GET /totals` with an `Authorization: Bearer <secret>` header returns the current
+/// [`ResponseTotals`] as JSON. The secret is never part of the URL, so it does not end up in
+/// access logs.
+pub struct WebhookReceiver {
+    server: Server,
+    state: Arc<State>,
+}
+
+struct State {
+    survey_id: usize,
+    secret: String,
+    store: ResponseStore,
+    received: Mutex<Received>,
+}
+
+/// The responses in the store.
+struct Received {
+    response_ids: HashSet<u64>,
+    totals: ResponseTotals,
+}
+
+#[derive(Debug, Deserialize)]
+struct Callback {
+    event: String,
+    survey_id: usize,
+    response: Response,
+}
+
+impl WebhookReceiver {
+    /// Starts receiving the callbacks for `survey_id` on `address`. The totals start with the
+    /// responses already in `store`.
+    pub fn start(
+        address: impl ToSocketAddrs,
+        survey_id: usize,
+        secret: String,
+        store: ResponseStore,
+    ) -> Result<Self> {
+        if secret.is_empty() {
+            return Err(Error::EmptyWebhookSecret);
+        }
+        let responses = store.load(survey_id)?;
+        let state = Arc::new(State {
+            survey_id,
+            secret,
+            store,
+            received: Mutex::new(Received {
+                response_ids: responses.iter().map(|r| r.response_id).collect(),
+                totals: ResponseTotals::new(&responses),
+            }),
+        });
+
+        let handler_state = state.clone();
+        let server = Server::start(
+            TcpListener::bind(address)?,
+            "Webhook receiver",
+            move |request| handle_request(&handler_state, request),
+        )?;
+        Ok(Self { server, state })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.server.address()
+    }
+
+    pub fn totals(&self) -> ResponseTotals {
+        self.state.received.lock().unwrap().totals.clone()
+    }
+
+    /// Receives callbacks until the process is stopped.
+    pub fn join(self) {
+        self.server.join();
+    }
+}
+
+fn handle_request(state: &State, request: Request) -> (u16, Value) {
+    let header = |name: &str| request.headers.get(name).map(String::as_str);
+    let authorized = match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/webhook") => header(SIGNATURE_HEADER).is_some_and(|sig| {
+            constant_time_eq(
+                sig.as_bytes(),
+                signature(&state.secret, &request.body).as_bytes(),
+            )
+        }),
+        ("GET", "/totals") => header("authorization")
+            .and_then(|a| a.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.as_bytes(), state.secret.as_bytes())),
+        _ => return (404, json!({ "error": "not found" })),
+    };
+    if !authorized {
+        log::warn!("Rejected an unauthorized {} request", request.method);
+        return (401, json!({ "error": "unauthorized" }));
+    }
+
+    match request.method.as_str() {
+        "POST" => receive(state, &request.body),
+        _ => (200, json!(state.received.lock().unwrap().totals)),
+    }
+}
+
+fn receive(state: &State, body: &str) -> (u16, Value) {
+    let callback: Callback = match serde_json::from_str(body) {
+        Ok(callback) => callback,
+        Err(error) => {
+            log::warn!("Rejected an invalid callback: {error}");
+            return (400, json!({ "error": error.to_string() }));
+        }
+    };
+    if callback.survey_id != state.survey_id {
+        log::warn!("Rejected a callback for survey {}", callback.survey_id);
+        return (400, json!({ "error": "unexpected survey" }));
+    }
+    if callback.event != RESPONSE_COMPLETED {
+        log::debug!("Ignoring {} event", callback.event);
+        return (200, json!({ "stored": false }));
+    }
+
+    let mut response = callback.response;
+    let mut received = state.received.lock().unwrap();
+    if !received.response_ids.insert(response.response_id) {
+        log::debug!("Response {} is already stored", response.response_id);
+        return (200, json!({ "stored": false }));
+    }
+    // Like `responses download`, only answers of known question kinds are stored
+    response.answers.retain(|a| a.question_id().is_some());
+    if let Err(error) = state.store.append(state.survey_id, &response) {
+        log::error!("{error:#}");
+        received.response_ids.remove(&response.response_id);
+        return (500, json!({ "error": "cannot store the response" }));
+    }
+    received.totals.add(&response);
+    log::info!(
+        "Stored response {} ({} in total, {} completed)",
+        response.response_id,
+        received.totals.responses,
+        received.totals.completed
+    );
+    (200, json!({ "stored": true }))
+}
+
+/// The signature of a callback with `body`: the HMAC-SHA256 of the body keyed with `secret`,
+/// hex-encoded.
+pub fn signature(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// Compares the signature or token without revealing the length of the matching prefix through timing.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        assert_eq!(
+            signature("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn requires_a_secret() {
+        let dir = TempDir::new("webhook");
+        let store = ResponseStore::new(dir.path());
+        assert!(matches!(
+            WebhookReceiver::start("127.0.0.1:0", 1, String::new(), store),
+            Err(Error::EmptyWebhookSecret)
+        ));
+    }
+
+    #[test]
+    fn compares_in_constant_time() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+    }
+}
@@ -0,0 +1,634 @@
+mod support;
+
+use support::mock::{Fixtures, MockServer};
+use support::{
+    check, check_args, client, fixture_dir, start_server, survey_dir, temp_dir, verifier,
+    verifier_command,
+};
+use surveyhero::api::{Client, TextKind};
+use surveyhero::fetch_survey_data;
+use surveyhero::responses::ResponseStore;
+use surveyhero::snapshot::{Snapshot, SnapshotStore};
+use surveyhero::webhook::{signature, WebhookReceiver, SIGNATURE_HEADER};
+
+#[test]
+fn fetches_survey_data() {
+    let server = start_server();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let data = runtime
+        .block_on(fetch_survey_data(&client(&server), 42))
+        .unwrap();
+
+    let texts = |questions: &[surveyhero::api::Question]| {
+        questions.iter().map(|q| q.text()).collect::<Vec<_>>()
+    };
+    assert_eq!(
+        texts(&data.main),
+        [
+            "Do you use Rust?",
+            "Why do you use Rust?",
+            "How do you rate these tools?"
+        ]
+    );
+    assert_eq!(data.elements.len(), 5);
+    assert_eq!(data.main[2].ids(TextKind::Row), [Some(301), Some(302)]);
+    assert_eq!(data.main[2].ids(TextKind::Column), [Some(31), Some(32)]);
+    // Fields the client does not interpret are kept
+    let question = serde_json::to_value(&data.main[0]).unwrap();
+    assert_eq!(question["is_required"], true);
+    assert_eq!(
+        question["choice_list"]["settings"]["randomize_choices"],
+        true
+    );
+    assert_eq!(data.conditions.len(), 1);
+    // Only active secondary languages are fetched
+    assert_eq!(data.secondary_languages.len(), 1);
+    assert_eq!(data.secondary_languages[0].0, "de");
+    assert_eq!(texts(&data.secondary_languages[0].1)[0], "Benutzt du Rust?");
+
+    let error = runtime
+        .block_on(fetch_survey_data(&client(&server), 1))
+        .unwrap_err();
+    assert!(matches!(
+        &error,
+        surveyhero::Error::UnknownSurvey { survey_id: 1, available } if available.len() == 2
+    ));
+    assert!(error.to_string().contains("id= 42 name= Mock survey"));
+}
+
+#[test]
+fn retries_failed_requests() {
+    let server = start_server();
+    server.fail_next(503, 2);
+    let mut client = Client::from(client(&server));
+    assert_eq!(client.fetch_surveys().unwrap().len(), 2);
+    // Two failures, the surveys and the empty page after them
+    assert_eq!(server.requests().len(), 4);
+
+    server.fail_next(500, 5);
+    assert!(matches!(
+        client.fetch_surveys(),
+        Err(surveyhero::Error::Http { status, .. }) if status == 500
+    ));
+
+    server.fail_next(429, 5);
+    assert!(matches!(
+        client.fetch_surveys(),
+        Err(surveyhero::Error::RateLimited {
+            retry_after: Some(_),
+            ..
+        })
+    ));
+
+    // Authentication failures are not retried
+    server.fail_next(401, 1);
+    assert!(matches!(
+        client.fetch_conditions(42),
+        Err(surveyhero::Error::Unauthorized { .. })
+    ));
+
+    // A failed POST may have created the link already, so it is only retried when rate limited
+    let requests = server.requests().len();
+    server.fail_next(503, 1);
+    assert!(matches!(
+        client.create_translation_link(42, "de"),
+        Err(surveyhero::Error::Http { status, .. }) if status == 503
+    ));
+    assert_eq!(server.requests().len(), requests + 1);
+    server.fail_next(429, 1);
+    assert!(client.create_translation_link(42, "de").is_ok());
+    assert_eq!(server.requests().len(), requests + 3);
+}
+
+#[test]
+fn paginates_list_endpoints() {
+    let fixtures = Fixtures::load(&fixture_dir()).unwrap().with_page_size(2);
+    let server = MockServer::start(fixtures).unwrap();
+    let mut client = Client::from(client(&server));
+    // The pages requested from an endpoint
+    let pages = |endpoint: &str| {
+        server
+            .requests()
+            .into_iter()
+            .filter_map(|r| {
+                let (path, query) = r.path.split_once('?')?;
+                let page = query.split('&').find_map(|p| p.strip_prefix("page="))?;
+                (path == endpoint).then(|| page.parse::<usize>().unwrap())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // A full last page is followed by an empty one
+    let responses = client.fetch_responses(42).unwrap();
+    assert_eq!(
+        responses.iter().map(|r| r.response_id).collect::<Vec<_>>(),
+        [100, 101]
+    );
+    assert_eq!(pages("/surveys/42/responses"), [1, 2]);
+    assert_eq!(client.fetch_surveys().unwrap().len(), 2);
+    assert_eq!(pages("/surveys"), [1, 2]);
+
+    // A shorter page is the last one
+    assert_eq!(client.fetch_elements(42, None).unwrap().len(), 5);
+    assert_eq!(pages("/surveys/42/elements"), [1, 2, 3]);
+    let questions = client.fetch_questions(42, Some("de".to_owned())).unwrap();
+    assert_eq!(questions.len(), 3);
+    assert_eq!(questions[0].text(), "Benutzt du Rust?");
+    assert_eq!(pages("/surveys/42/questions"), [1, 2]);
+}
+
+#[test]
+fn stops_at_repeated_pages() {
+    let fixtures = Fixtures::load(&fixture_dir())
+        .unwrap()
+        .with_page_size(2)
+        .ignoring_pages();
+    let server = MockServer::start(fixtures).unwrap();
+    let mut client = Client::from(client(&server));
+    // Every page contains the first two responses, the second one is recognized as a repetition
+    let responses = client.fetch_responses(42).unwrap();
+    assert_eq!(
+        responses.iter().map(|r| r.response_id).collect::<Vec<_>>(),
+        [100, 101]
+    );
+    let questions = client.fetch_questions(42, Some("de".to_owned())).unwrap();
+    assert_eq!(questions.len(), 2);
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[test]
+fn receives_webhook_callbacks() {
+    let store = ResponseStore::new(temp_dir("webhook"));
+    let responses = Client::from(client(&start_server()))
+        .fetch_responses(42)
+        .unwrap();
+    store.write_all(42, &responses[..1]).unwrap();
+    let receiver =
+        WebhookReceiver::start("127.0.0.1:0", 42, "secret".to_owned(), store.clone()).unwrap();
+    let url = |path: &str| format!("http://{}{path}", receiver.address());
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let http = reqwest::Client::new();
+    let post = |secret: &str, body: &serde_json::Value| {
+        let body = body.to_string();
+        let request = http
+            .post(url("/webhook"))
+            .header(SIGNATURE_HEADER, signature(secret, &body))
+            .body(body);
+        runtime.block_on(request.send()).unwrap()
+    };
+    let callback = |survey_id: usize| {
+        serde_json::json!({
+            "event": "response.completed",
+            "survey_id": survey_id,
+            "response": responses[1],
+        })
+    };
+
+    assert_eq!(post("wrong", &callback(42)).status(), 401);
+    let unsigned = http.post(url("/webhook")).json(&callback(42));
+    assert_eq!(runtime.block_on(unsigned.send()).unwrap().status(), 401);
+    let token_in_url = http.post(url("/webhook?token=secret")).json(&callback(42));
+    assert_eq!(runtime.block_on(token_in_url.send()).unwrap().status(), 404);
+    assert_eq!(post("secret", &callback(7)).status(), 400);
+    assert_eq!(post("secret", &callback(42)).status(), 200);
+    // SurveyHero retries callbacks, the response is only stored once
+    assert_eq!(post("secret", &callback(42)).status(), 200);
+    assert_eq!(
+        store
+            .load(42)
+            .unwrap()
+            .iter()
+            .map(|r| r.response_id)
+            .collect::<Vec<_>>(),
+        [100, 101]
+    );
+
+    let totals: serde_json::Value = runtime
+        .block_on(async {
+            http.get(url("/totals"))
+                .bearer_auth("secret")
+                .send()
+                .await?
+                .json()
+                .await
+        })
+        .unwrap();
+    assert_eq!(totals["responses"], 2);
+    assert_eq!(totals["per_language"]["de"], 1);
+    assert_eq!(totals["per_question"]["1"], 2);
+    assert_eq!(totals["per_question"]["2"], 1);
+}
+
+#[test]
+fn check_reports_drift() {
+    let server = start_server();
+    let dir = survey_dir("check", true);
+
+    // The missing translation is created from SurveyHero, so there is no drift
+    let output = check(&server, &dir, &[]);
+    assert!(output.status.success(), "{:?}", output);
+    let translation = std::fs::read_to_string(dir.join("translations/de.md")).unwrap();
+    assert!(translation.contains("### Benutzt du Rust?"));
+
+    let questions = std::fs::read_to_string(dir.join("questions.md")).unwrap();
+    std::fs::write(
+        dir.join("questions.md"),
+        questions.replace("- Cargo", "- Rustup"),
+    )
+    .unwrap();
+    let output = check(&server, &dir, &["--format", "json"]);
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let findings = report["findings"].as_array().unwrap();
+    assert_eq!(findings.len(), 1, "{findings:?}");
+    assert_eq!(findings[0]["kind"], "matrix-answer-differs");
+    assert_eq!(findings[0]["md"], "Rustup");
+    assert_eq!(findings[0]["sh"], "Cargo");
+}
+
+#[test]
+fn reports_unsupported_question_types() {
+    // A copy of the fixtures with a slider question, which the verifier does not support
+    let fixtures = temp_dir("unsupported-fixtures");
+    std::fs::copy(
+        fixture_dir().join("surveys.json"),
+        fixtures.join("surveys.json"),
+    )
+    .unwrap();
+    std::fs::create_dir(fixtures.join("42")).unwrap();
+    for entry in std::fs::read_dir(fixture_dir().join("42")).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        if name.starts_with("elements") {
+            let mut elements: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+            elements.insert(
+                4,
+                serde_json::json!({
+                    "type": "question",
+                    "question": {
+                        "type": "slider",
+                        "question_id": 4,
+                        "question_text": "How satisfied are you with Rust?",
+                        "description_text": "",
+                        "slider": { "min": 0, "max": 10 }
+                    }
+                }),
+            );
+            text = serde_json::to_string(&elements).unwrap();
+        }
+        std::fs::write(fixtures.join("42").join(name), text).unwrap();
+    }
+    let server = MockServer::start(Fixtures::load(&fixtures).unwrap()).unwrap();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let data = runtime
+        .block_on(fetch_survey_data(&client(&server), 42))
+        .unwrap();
+    assert!(matches!(
+        &data.main[2],
+        surveyhero::api::Question::Unknown { kind, raw, .. }
+            if kind == "slider" && raw["slider"]["max"] == 10
+    ));
+
+    let dir = temp_dir("unsupported");
+    let questions = std::fs::read_to_string(fixture_dir().join("questions.md")).unwrap();
+    let questions = questions.replace(
+        "### How do you rate",
+        "### How satisfied are you with Rust?\n\nType: unsupported (slider)\n\n### How do you rate",
+    );
+    std::fs::write(dir.join("questions.md"), &questions).unwrap();
+    let output = check(&server, &dir, &[]);
+    assert!(output.status.success(), "{:?}", output);
+
+    std::fs::write(
+        dir.join("questions.md"),
+        questions.replace("unsupported (slider)", "free form"),
+    )
+    .unwrap();
+    let output = check(&server, &dir, &["--format", "json"]);
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let findings = report["findings"].as_array().unwrap();
+    assert!(
+        findings.iter().any(|f| f["kind"] == "unsupported-question"
+            && f["md"] == "free form"
+            && f["sh"] == "slider"),
+        "{:?}",
+        findings
+    );
+}
+
+#[test]
+fn lists_surveys() {
+    let server = start_server();
+    let output = verifier(&server, &["surveys"]);
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2, "{}", stdout);
+    assert!(
+        lines[0].starts_with("42         open      3 lang.      2 resp."),
+        "{}",
+        stdout
+    );
+    assert!(lines[0].ends_with("modified 2025-10-15  Mock survey"));
+    // The list contains the metadata, only the languages are fetched per survey
+    let mut paths = server
+        .requests()
+        .into_iter()
+        .map(|r| r.path)
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "/surveys/42/languages",
+            "/surveys/7/languages",
+            "/surveys?page=1",
+            "/surveys?page=2"
+        ]
+    );
+
+    let output = verifier(&server, &["surveys", "--survey-id", "42", "--json"]);
+    assert!(output.status.success(), "{:?}", output);
+    let surveys: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(surveys[0]["status"], "open");
+    assert_eq!(
+        surveys[0]["languages"],
+        serde_json::json!(["en", "de", "fr"])
+    );
+}
+
+#[test]
+fn manages_translation_links() {
+    let server = start_server();
+    let links = |server: &MockServer| {
+        let output = verifier(server, &["translation-links", "list", "--survey-id", "42"]);
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // The survey is open and the German link is still active
+    let output = verifier(
+        &server,
+        &["translation-links", "check", "--survey-id", "42"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("translate/500"));
+
+    let output = verifier(
+        &server,
+        &[
+            "translation-links",
+            "create",
+            "--survey-id",
+            "42",
+            "--lang",
+            "de",
+            "--lang",
+            "fr",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("de already has an active"));
+    assert_eq!(links(&server).lines().count(), 2);
+
+    let output = verifier(
+        &server,
+        &["translation-links", "revoke", "--survey-id", "42", "--all"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(links(&server), "");
+    let output = verifier(
+        &server,
+        &["translation-links", "check", "--survey-id", "42"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+}
+
+#[test]
+fn pushes_changed_questions() {
+    let server = start_server();
+    let dir = survey_dir("push", false);
+    let questions = std::fs::read_to_string(dir.join("questions.md")).unwrap();
+    std::fs::write(
+        dir.join("questions.md"),
+        questions.replace("- Cargo", "- Rustup"),
+    )
+    .unwrap();
+    let args = ["push", "--survey-id", "42", "--path", dir.to_str().unwrap()];
+
+    let output = verifier(&server, &args);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(server.requests().iter().all(|r| r.method == "GET"));
+
+    let output = verifier(&server, &[&args[..], &["--apply"]].concat());
+    assert!(output.status.success(), "{:?}", output);
+    let updates: Vec<_> = server
+        .requests()
+        .into_iter()
+        .filter(|r| r.method == "PUT")
+        .collect();
+    assert_eq!(updates.len(), 1, "{:?}", updates);
+    assert!(updates[0].body.contains("Rustup"), "{}", updates[0].body);
+
+    // A question inserted between existing ones blocks the push
+    std::fs::write(
+        dir.join("questions.md"),
+        questions.replace(
+            "### Why do you use Rust?",
+            "### Since when do you use Rust?\n\nType: free form\n\n### Why do you use Rust?",
+        ),
+    )
+    .unwrap();
+    let requests = server.requests().len();
+    let output = verifier(&server, &[&args[..], &["--apply"]].concat());
+    assert!(!output.status.success(), "{:?}", output);
+    assert!(server.requests()[requests..]
+        .iter()
+        .all(|r| r.method == "GET"));
+}
+
+#[test]
+fn download_creates_and_keeps_translations() {
+    let server = start_server();
+    let dir = survey_dir("download", false);
+    let path = dir.to_str().unwrap();
+
+    let output = verifier(&server, &["download", "--survey-id", "42", "--path", path]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Created"));
+    let translation = std::fs::read_to_string(dir.join("translations/de.md")).unwrap();
+    assert!(translation.contains("- Schlecht"));
+    // The English file is never overwritten
+    assert_eq!(
+        std::fs::read_to_string(dir.join("questions.md")).unwrap(),
+        std::fs::read_to_string(fixture_dir().join("questions.md")).unwrap()
+    );
+
+    let output = verifier(&server, &["download", "--survey-id", "42", "--path", path]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unchanged"));
+}
+
+#[test]
+fn replays_recorded_check() {
+    let dir = survey_dir("replay", true);
+    let args = check_args(&dir);
+
+    let server = start_server();
+    let output = check(&server, &dir, &["--record"]);
+    assert!(output.status.success(), "{:?}", output);
+    let requests = server.requests().len();
+    drop(server);
+    let cassette = std::fs::read_to_string(dir.join("surveyhero.cassette.json")).unwrap();
+    assert!(!cassette.contains("token"));
+
+    // The server is gone and there are no credentials, everything comes from the cassette
+    let output = verifier_command()
+        .args(&args)
+        .arg("--replay")
+        .env("SH_API_BASE_URL", "http://127.0.0.1:1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(requests >= 5);
+
+    std::fs::remove_file(dir.join("surveyhero.cassette.json")).unwrap();
+    let output = verifier_command()
+        .args(&args)
+        .arg("--replay")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("record it first with --record"));
+}
+
+#[test]
+fn records_single_file_surveys() {
+    let dir = survey_dir("replay-file", false);
+    let file = dir.join("questions.md");
+    let args = [
+        "push",
+        "--survey-id",
+        "42",
+        "--path",
+        file.to_str().unwrap(),
+    ];
+
+    let server = start_server();
+    let output = verifier(&server, &[&args[..], &["--record"]].concat());
+    assert!(output.status.success(), "{:?}", output);
+    drop(server);
+    assert!(dir.join("questions.surveyhero.cassette.json").is_file());
+
+    let output = verifier_command()
+        .args(args)
+        .arg("--replay")
+        .env("SH_API_BASE_URL", "http://127.0.0.1:1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+}
+
+#[test]
+fn checks_offline_and_diffs_snapshots() {
+    let dir = survey_dir("snapshots", true);
+    let snapshots = dir.join("store");
+    let snapshot_args = ["--snapshots", snapshots.to_str().unwrap()];
+
+    let server = start_server();
+    let output = check(&server, &dir, &snapshot_args);
+    assert!(output.status.success(), "{:?}", output);
+    let requests = server.requests().len();
+    let output = check(
+        &server,
+        &dir,
+        &[&snapshot_args[..], &["--max-age", "1h"]].concat(),
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(server.requests().len(), requests);
+    drop(server);
+
+    let store = SnapshotStore::new(&snapshots);
+    assert_eq!(store.list(42).unwrap().len(), 1);
+    let output = verifier_command()
+        .args(check_args(&dir))
+        .args(snapshot_args)
+        .arg("--offline")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    // A question renamed on SurveyHero shows up in the diff
+    let mut snapshot = store.latest(42).unwrap().unwrap();
+    let json = serde_json::to_string(&snapshot.data).unwrap();
+    snapshot = Snapshot {
+        taken_at: snapshot.taken_at + 1,
+        data: serde_json::from_str(&json.replace("Why do you use", "Why do you love")).unwrap(),
+        ..snapshot
+    };
+    store.save(&snapshot).unwrap();
+    let output = verifier_command()
+        .args(["snapshot", "diff", "--survey-id", "42", "--snapshots"])
+        .arg(&snapshots)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert!(diff.contains("-### Why do you use Rust?"), "{}", diff);
+    assert!(diff.contains("+### Why do you love Rust?"), "{}", diff);
+    assert!(!diff.contains("translations/de.md"), "{}", diff);
+}
+
+#[test]
+fn reads_credentials_from_dot_env() {
+    let server = start_server();
+    let dir = temp_dir("credentials");
+    let verifier = || {
+        verifier_command()
+            .args([
+                "responses",
+                "download",
+                "--survey-id",
+                "42",
+                "--store",
+                "store",
+            ])
+            .current_dir(&dir)
+            .env_remove("SH_API_USER")
+            .env_remove("SH_API_TOKEN")
+            .env_remove("SH_API_CREDENTIAL_HELPER")
+            .env_remove("XDG_CONFIG_HOME")
+            .env("HOME", &dir)
+            .env("SH_API_BASE_URL", server.base_url())
+            .output()
+            .unwrap()
+    };
+
+    let output = verifier();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("no SurveyHero API credentials found"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("SH_API_CREDENTIAL_HELPER is not set"),
+        "{}",
+        stderr
+    );
+    assert!(server.requests().is_empty());
+
+    std::fs::write(
+        dir.join(".env"),
+        "# SurveyHero API key\nexport SH_API_USER=\"user\"\nexport SH_API_TOKEN=\"token\"\n",
+    )
+    .unwrap();
+    let output = verifier();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.join("store/42.jsonl").exists());
+}
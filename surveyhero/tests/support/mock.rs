@@ -0,0 +1,408 @@
+use anyhow::Context;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// The data served by a [`MockServer`].
+#[derive(Debug, Clone, Default)]
+pub struct Fixtures {
+    surveys: Vec<Value>,
+    survey_data: HashMap<u64, SurveyFixtures>,
+    page_size: usize,
+    ignore_pages: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SurveyFixtures {
+    languages: Vec<Value>,
+    conditions: Vec<Value>,
+    /// Elements per language code, `None` for the default language.
+    elements: HashMap<Option<String>, Vec<Value>>,
+    responses: Vec<Value>,
+    translation_links: Vec<Value>,
+}
+
+impl Fixtures {
+    /// Loads fixtures from a directory with the following JSON files, each containing an array
+    /// of objects in the format of the SurveyHero API:
+    ///
+    /// - `surveys.json`: the surveys of the account, with their status, dates and number of
+    ///   responses
+    /// - `<survey_id>/elements.json`: the elements of the survey in the default language, and
+    ///   `<survey_id>/elements.<lang>.json` for every secondary language
+    /// - `<survey_id>/languages.json`, `<survey_id>/conditions.json`,
+    ///   `<survey_id>/responses.json` and `<survey_id>/translation_links.json` (optional)
+    ///
+    /// The questions endpoint serves the questions of the elements.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let mut fixtures = Fixtures {
+            surveys: read_array(&dir.join("surveys.json"))?,
+            survey_data: HashMap::new(),
+            page_size: 100,
+            ignore_pages: false,
+        };
+        for survey in &fixtures.surveys {
+            let Some(survey_id) = survey["survey_id"].as_u64() else {
+                anyhow::bail!("survey without survey_id in {}", dir.display());
+            };
+            let survey_dir = dir.join(survey_id.to_string());
+            if !survey_dir.is_dir() {
+                // A survey without elements or languages
+                fixtures
+                    .survey_data
+                    .insert(survey_id, SurveyFixtures::default());
+                continue;
+            }
+            let optional = |name: &str| {
+                let path = survey_dir.join(name);
+                if path.exists() {
+                    read_array(&path)
+                } else {
+                    Ok(vec![])
+                }
+            };
+            let mut data = SurveyFixtures {
+                languages: optional("languages.json")?,
+                conditions: optional("conditions.json")?,
+                elements: HashMap::new(),
+                responses: optional("responses.json")?,
+                translation_links: optional("translation_links.json")?,
+            };
+            for entry in std::fs::read_dir(&survey_dir)? {
+                let path = entry?.path();
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let Some(rest) = name.strip_prefix("elements") else {
+                    continue;
+                };
+                let language = match rest.strip_suffix(".json") {
+                    Some("") => None,
+                    Some(language) => Some(language.trim_start_matches('.').to_owned()),
+                    None => continue,
+                };
+                data.elements.insert(language, read_array(&path)?);
+            }
+            fixtures.survey_data.insert(survey_id, data);
+        }
+        Ok(fixtures)
+    }
+
+    /// Number of items per page of the surveys, elements, questions and responses endpoints
+    /// (100 by default).
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Makes the list endpoints ignore the `page` parameter and always return the first page.
+    pub fn ignoring_pages(mut self) -> Self {
+        self.ignore_pages = true;
+        self
+    }
+}
+
+fn read_array(path: &Path) -> anyhow::Result<Vec<Value>> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Cannot parse {}", path.display()))
+}
+
+/// A request received by a [`MockServer`].
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query, relative to the base URL.
+    pub path: String,
+    pub body: String,
+}
+
+/// An in-process HTTP server imitating the SurveyHero API, for tests without network access
+/// or credentials. Point a client at it with `with_base_url(server.base_url())` or the
+/// `SH_API_BASE_URL` environment variable.
+///
+/// Every request needs basic authentication (with any credentials). Question updates and
+/// creations are accepted and recorded, but do not change the fixtures. Translation links can
+/// be created and revoked.
+pub struct MockServer {
+    address: SocketAddr,
+    state: Arc<State>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct State {
+    fixtures: Fixtures,
+    requests: Mutex<Vec<RecordedRequest>>,
+    failures: Mutex<VecDeque<u16>>,
+    /// ID of the next created question or translation link.
+    next_id: AtomicU64,
+    /// The current translation links per survey, starting with those of the fixtures.
+    translation_links: Mutex<HashMap<u64, Vec<Value>>>,
+    shutdown: AtomicBool,
+}
+
+impl MockServer {
+    /// Starts serving `fixtures` on a free local port.
+    pub fn start(fixtures: Fixtures) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let translation_links = fixtures
+            .survey_data
+            .iter()
+            .map(|(survey_id, data)| (*survey_id, data.translation_links.clone()))
+            .collect();
+        let state = Arc::new(State {
+            fixtures,
+            requests: Mutex::new(vec![]),
+            failures: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(1_000_000),
+            translation_links: Mutex::new(translation_links),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let thread_state = state.clone();
+        let thread = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_state.shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let state = thread_state.clone();
+                std::thread::spawn(move || {
+                    if let Err(error) = handle_connection(stream, &state) {
+                        log::warn!("Mock server connection failed: {error}");
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            address,
+            state,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// All requests received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Answers the next `count` requests with `status` (and `Retry-After: 0`) instead of the
+    /// fixtures, e.g. to test retries.
+    pub fn fail_next(&self, status: u16, count: usize) {
+        self.state
+            .failures
+            .lock()
+            .unwrap()
+            .extend(std::iter::repeat_n(status, count));
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.state.shutdown.store(true, Ordering::SeqCst);
+        // Wake up the accept loop
+        let _ = TcpStream::connect(self.address);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, state: &State) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(());
+    };
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_owned());
+        }
+    }
+    let length = headers
+        .get("content-length")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    let body = String::from_utf8_lossy(&body).into_owned();
+
+    state.requests.lock().unwrap().push(RecordedRequest {
+        method: method.to_owned(),
+        path: path.to_owned(),
+        body: body.clone(),
+    });
+
+    let failure = state.failures.lock().unwrap().pop_front();
+    let (status, body) = if let Some(status) = failure {
+        (status, json!({ "error": "injected failure" }))
+    } else if !headers
+        .get("authorization")
+        .is_some_and(|a| a.starts_with("Basic "))
+    {
+        (401, json!({ "error": "unauthorized" }))
+    } else {
+        route(state, method, path, &body)
+    };
+
+    let body = body.to_string();
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Retry-After: 0\r\nConnection: close\r\n\r\n{body}",
+        reason(status),
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn route(state: &State, method: &str, path: &str, body: &str) -> (u16, Value) {
+    let fixtures = &state.fixtures;
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect::<HashMap<_, _>>();
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let not_found = (404, json!({ "error": "not found" }));
+    // The requested page of a list, the first one without a `page` parameter
+    let page = |items: &[Value]| {
+        let page = query
+            .get("page")
+            .and_then(|p| p.parse::<usize>().ok())
+            .filter(|_| !fixtures.ignore_pages)
+            .unwrap_or(1)
+            .max(1);
+        items
+            .iter()
+            .skip((page - 1) * fixtures.page_size)
+            .take(fixtures.page_size)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    if let ("GET", ["surveys"]) = (method, segments.as_slice()) {
+        return (200, json!({ "surveys": page(&fixtures.surveys) }));
+    }
+    if let ("GET", ["surveys", survey_id]) = (method, segments.as_slice()) {
+        let survey_id = survey_id.parse::<u64>().ok();
+        return match fixtures
+            .surveys
+            .iter()
+            .find(|s| s["survey_id"].as_u64() == survey_id)
+        {
+            Some(survey) => (200, json!({ "survey": survey })),
+            None => not_found,
+        };
+    }
+    let ["surveys", survey_id, rest @ ..] = segments.as_slice() else {
+        return not_found;
+    };
+    let Some((survey_id, survey)) = survey_id
+        .parse()
+        .ok()
+        .and_then(|id: u64| Some((id, fixtures.survey_data.get(&id)?)))
+    else {
+        return not_found;
+    };
+    let language = query.get("lang").map(|l| l.to_string());
+    let elements = || survey.elements.get(&language).cloned();
+
+    match (method, rest) {
+        ("GET", ["languages"]) => (200, json!({ "languages": survey.languages })),
+        ("GET", ["conditions"]) => (200, json!({ "conditions": survey.conditions })),
+        ("GET", ["elements"]) => match elements() {
+            Some(elements) => (200, json!({ "elements": page(&elements) })),
+            None => not_found,
+        },
+        ("GET", ["questions"]) => match elements() {
+            Some(mut elements) => {
+                elements.retain(|e| e["type"] == "question");
+                (200, json!({ "elements": page(&elements) }))
+            }
+            None => not_found,
+        },
+        ("GET", ["responses"]) => (200, json!({ "responses": page(&survey.responses) })),
+        ("POST", ["questions"]) => {
+            let Ok(mut question) = serde_json::from_str::<Value>(body) else {
+                return (400, json!({ "error": "invalid JSON" }));
+            };
+            question["question_id"] = state.next_id.fetch_add(1, Ordering::SeqCst).into();
+            if question.get("description_text").is_none() {
+                question["description_text"] = "".into();
+            }
+            (201, json!({ "question": question }))
+        }
+        ("PUT", ["questions", _]) => match serde_json::from_str::<Value>(body) {
+            Ok(_) => (200, json!({})),
+            Err(_) => (400, json!({ "error": "invalid JSON" })),
+        },
+        ("GET", ["translation_links"]) => {
+            let links = state.translation_links.lock().unwrap();
+            (200, json!({ "translation_links": links.get(&survey_id) }))
+        }
+        ("POST", ["translation_links"]) => {
+            let Some(language) = serde_json::from_str::<Value>(body)
+                .ok()
+                .and_then(|body| body["language"].as_str().map(str::to_owned))
+            else {
+                return (400, json!({ "error": "language missing" }));
+            };
+            let link_id = state.next_id.fetch_add(1, Ordering::SeqCst);
+            let link = json!({
+                "link_id": link_id,
+                "language": language,
+                "url": format!("https://surveyhero.com/translate/{link_id}"),
+                "is_active": true,
+            });
+            let mut links = state.translation_links.lock().unwrap();
+            links.entry(survey_id).or_default().push(link.clone());
+            (201, json!({ "translation_link": link }))
+        }
+        ("DELETE", ["translation_links", link_id]) => {
+            let mut links = state.translation_links.lock().unwrap();
+            let links = links.entry(survey_id).or_default();
+            let (count, link_id) = (links.len(), link_id.parse::<u64>().ok());
+            links.retain(|link| link["link_id"].as_u64() != link_id);
+            if links.len() == count {
+                return not_found;
+            }
+            (200, json!({}))
+        }
+        _ => not_found,
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
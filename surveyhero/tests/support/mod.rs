@@ -0,0 +1,81 @@
+pub mod mock;
+
+use mock::{Fixtures, MockServer};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
+use surveyhero::api::AsyncClient;
+use surveyhero::retry::RetryPolicy;
+
+pub fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mock")
+}
+
+pub fn start_server() -> MockServer {
+    MockServer::start(Fixtures::load(&fixture_dir()).unwrap()).unwrap()
+}
+
+pub fn client(server: &MockServer) -> AsyncClient {
+    AsyncClient::new("user".to_owned(), "token".to_owned())
+        .with_base_url(server.base_url())
+        .with_retry_policy(RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        })
+}
+
+/// A fresh directory for the files of one test.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A fresh survey directory with a copy of the fixture questions and, if `translations` is set,
+/// an empty `translations` directory.
+pub fn survey_dir(name: &str, translations: bool) -> PathBuf {
+    let dir = temp_dir(name);
+    std::fs::copy(fixture_dir().join("questions.md"), dir.join("questions.md")).unwrap();
+    if translations {
+        std::fs::create_dir(dir.join("translations")).unwrap();
+    }
+    dir
+}
+
+/// The verifier binary, run in the target directory without SurveyHero credentials.
+pub fn verifier_command() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_verifier"));
+    command
+        .current_dir(env!("CARGO_TARGET_TMPDIR"))
+        .env_remove("SH_API_USER")
+        .env_remove("SH_API_TOKEN");
+    command
+}
+
+/// Runs the verifier against `server`.
+pub fn verifier(server: &MockServer, args: &[&str]) -> Output {
+    verifier_command()
+        .args(args)
+        .env("SH_API_USER", "user")
+        .env("SH_API_TOKEN", "token")
+        .env("SH_API_BASE_URL", server.base_url())
+        .output()
+        .unwrap()
+}
+
+/// The arguments of `verifier check` for survey 42 in `dir`.
+pub fn check_args(dir: &Path) -> Vec<&str> {
+    vec![
+        "check",
+        "--survey-id",
+        "42",
+        "--path",
+        dir.to_str().unwrap(),
+    ]
+}
+
+/// Runs `verifier check` for survey 42 in `dir` against `server`, with `args` added.
+pub fn check(server: &MockServer, dir: &Path, args: &[&str]) -> Output {
+    verifier(server, &[&check_args(dir)[..], args].concat())
+}